@@ -0,0 +1,97 @@
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use game_of_life::{
+    next_generation,
+    rule::Rule,
+    universe::{Dense, DoubleBuffer, Edges, Neighborhood, Universe},
+};
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+const SIZES: [usize; 3] = [64, 256, 512];
+const DENSITIES: [f64; 3] = [0.1, 0.3, 0.5];
+// side of the field stepped on a growing number of threads
+const SCALING_SIZE: usize = 1000;
+// side of the field holding a single glider
+const GLIDER_FIELD_SIZE: u16 = 500;
+
+fn random_field(size: usize, density: f64) -> Vec<Vec<bool>> {
+    let mut rng = StdRng::seed_from_u64(0);
+    (0..size)
+        .map(|_| (0..size).map(|_| rng.random_bool(density)).collect())
+        .collect()
+}
+
+fn step(c: &mut Criterion) {
+    // the binary runs on one thread unless --threads says otherwise
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(1)
+        .build_global()
+        .expect("the global thread pool is not built yet");
+
+    let rule = Rule::default();
+    let mut group = c.benchmark_group("next_generation");
+    for size in SIZES {
+        for density in DENSITIES {
+            let field = random_field(size, density);
+            group.bench_with_input(
+                BenchmarkId::new(format!("{size}x{size}"), density),
+                &field,
+                |b, field| b.iter(|| next_generation(field, &rule, true)),
+            );
+        }
+    }
+    group.finish();
+
+    // the same steps without converting or allocating the fields
+    let neighborhood = Neighborhood::Moore.offsets();
+    let mut group = c.benchmark_group("double_buffer");
+    for size in SIZES {
+        for density in DENSITIES {
+            let mut buffers = DoubleBuffer::new(Dense::from_cells(&random_field(size, density)));
+            group.bench_function(BenchmarkId::new(format!("{size}x{size}"), density), |b| {
+                b.iter(|| buffers.step(&rule, Edges::Wrap, &neighborhood))
+            });
+        }
+    }
+    group.finish();
+
+    // the same field on one thread and more, up to one per core, in pools of
+    // their own
+    let field = Dense::from_cells(&random_field(SCALING_SIZE, 0.3));
+    let cores = std::thread::available_parallelism().map_or(1, usize::from);
+    let mut group = c.benchmark_group(format!("threads/{SCALING_SIZE}x{SCALING_SIZE}"));
+    let mut thread_counts = vec![1, 2, 4, cores];
+    thread_counts.sort_unstable();
+    thread_counts.dedup();
+    for threads in thread_counts {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .expect("a thread pool can be built");
+        let mut buffers = DoubleBuffer::new(field.clone());
+        group.bench_function(BenchmarkId::from_parameter(threads), |b| {
+            b.iter(|| pool.install(|| buffers.step(&rule, Edges::Wrap, &neighborhood)))
+        });
+    }
+    group.finish();
+
+    // a lone glider on the dense engine, scanning every word of the field or
+    // only the cells around living ones; listed in another order, the Moore
+    // neighbors are not recognized by the word stepper and take the second
+    // path
+    let mut dense = Dense::new(GLIDER_FIELD_SIZE, GLIDER_FIELD_SIZE);
+    for (x, y) in [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)] {
+        dense.set(x, y, true);
+    }
+    let reordered: Vec<(i64, i64)> = neighborhood.iter().rev().copied().collect();
+    let mut group = c.benchmark_group(format!("glider/{GLIDER_FIELD_SIZE}x{GLIDER_FIELD_SIZE}"));
+    for (name, neighborhood) in [("whole field", &neighborhood), ("active cells", &reordered)] {
+        let mut buffers = DoubleBuffer::new(dense.clone());
+        group.bench_function(name, |b| {
+            b.iter(|| buffers.step(&rule, Edges::Wrap, neighborhood))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, step);
+criterion_main!(benches);
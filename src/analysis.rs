@@ -0,0 +1,196 @@
+//! Classifying a pattern by how it repeats: as a still life, an oscillator
+//! or a spaceship.
+//!
+//! The pattern runs on the infinite plane until its shape comes back,
+//! wherever it is; how long that took is the period and how far the shape
+//! moved meanwhile is the displacement.
+
+use std::{collections::HashMap, fmt};
+
+use crate::{
+    life::Board,
+    rule::Rule,
+    universe::{Edges, Sparse},
+};
+
+// the living cells of a generation from their top-left corner, sorted
+type Shape = Vec<(i64, i64)>;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Behavior {
+    DiesOut {
+        generation: u64,
+    },
+    // `from` is the first generation of the repeating shapes
+    StillLife {
+        from: u64,
+    },
+    Oscillator {
+        from: u64,
+        period: u64,
+    },
+    Spaceship {
+        from: u64,
+        period: u64,
+        displacement: (i64, i64),
+    },
+    // no shape came back within this many generations
+    Unsettled {
+        generations: u64,
+    },
+}
+
+impl fmt::Display for Behavior {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let from = match *self {
+            Behavior::DiesOut { generation } => {
+                return write!(f, "dies out at generation {generation}");
+            }
+            Behavior::Unsettled { generations } => {
+                return write!(f, "no period within {generations} generations");
+            }
+            Behavior::StillLife { from } => {
+                write!(f, "still life")?;
+                from
+            }
+            Behavior::Oscillator { from, period } => {
+                write!(f, "oscillator of period {period}")?;
+                from
+            }
+            Behavior::Spaceship {
+                from,
+                period,
+                displacement: (dx, dy),
+            } => {
+                let cells = dx.unsigned_abs().max(dy.unsigned_abs());
+                let divisor = gcd(cells, period);
+                let speed = match (cells / divisor, period / divisor) {
+                    (1, 1) => "c".to_string(),
+                    (cells, 1) => format!("{cells}c"),
+                    (1, period) => format!("c/{period}"),
+                    (cells, period) => format!("{cells}c/{period}"),
+                };
+                let direction = if dx == 0 || dy == 0 {
+                    "orthogonally"
+                } else if dx.abs() == dy.abs() {
+                    "diagonally"
+                } else {
+                    "obliquely"
+                };
+                write!(
+                    f,
+                    "spaceship of period {period} moving ({dx}, {dy}), at {speed} {direction}"
+                )?;
+                from
+            }
+        };
+        if 0 < from {
+            write!(f, " from generation {from}")?;
+        }
+        Ok(())
+    }
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+/// Runs the living `cells` on the infinite plane for up to `generations`
+/// generations, until the shape of one generation comes back.
+pub fn analyze(
+    cells: &[(i64, i64)],
+    rule: &Rule,
+    neighborhood: &[(i64, i64)],
+    generations: u64,
+) -> anyhow::Result<Behavior> {
+    rule.check_infinite()?;
+    let mut board = Board {
+        universe: Box::new(Sparse::default()),
+        rule: *rule,
+        neighborhood: neighborhood.to_vec(),
+        edges: Edges::Dead,
+    };
+    for &(x, y) in cells {
+        board.set(x, y, true);
+    }
+
+    // the generation and top-left corner of each shape seen
+    let mut shapes: HashMap<Shape, (u64, (i64, i64))> = HashMap::new();
+    for generation in 0..=generations {
+        let mut cells = board.universe.live_cells();
+        let (Some(left), Some(top)) = (
+            cells.iter().map(|&(x, _)| x).min(),
+            cells.iter().map(|&(_, y)| y).min(),
+        ) else {
+            return Ok(Behavior::DiesOut { generation });
+        };
+        for (x, y) in &mut cells {
+            *x -= left;
+            *y -= top;
+        }
+        cells.sort_unstable();
+
+        if let Some(&(from, (first_left, first_top))) = shapes.get(&cells) {
+            let period = generation - from;
+            return Ok(match (left - first_left, top - first_top) {
+                (0, 0) if period == 1 => Behavior::StillLife { from },
+                (0, 0) => Behavior::Oscillator { from, period },
+                displacement => Behavior::Spaceship {
+                    from,
+                    period,
+                    displacement,
+                },
+            });
+        }
+        shapes.insert(cells, (generation, (left, top)));
+        board.step();
+    }
+    Ok(Behavior::Unsettled { generations })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::universe::Neighborhood;
+
+    fn behavior(cells: &[(i64, i64)]) -> Behavior {
+        analyze(cells, &Rule::CONWAY, &Neighborhood::Moore.offsets(), 100)
+            .expect("Conway's rule leaves the empty plane empty")
+    }
+
+    #[test]
+    fn patterns_are_told_apart_by_how_they_repeat() {
+        assert_eq!(
+            behavior(&[(0, 0), (1, 0), (0, 1), (1, 1)]),
+            Behavior::StillLife { from: 0 }
+        );
+        assert_eq!(
+            behavior(&[(0, 0), (1, 0), (2, 0)]),
+            Behavior::Oscillator { from: 0, period: 2 }
+        );
+        assert_eq!(
+            behavior(&[(0, 0), (1, 0)]),
+            Behavior::DiesOut { generation: 1 }
+        );
+
+        let glider = behavior(&[(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]);
+        assert_eq!(
+            glider,
+            Behavior::Spaceship {
+                from: 0,
+                period: 4,
+                displacement: (1, 1)
+            }
+        );
+        assert_eq!(
+            glider.to_string(),
+            "spaceship of period 4 moving (1, 1), at c/4 diagonally"
+        );
+
+        // a pre-block settles into a block a generation in
+        assert_eq!(
+            behavior(&[(0, 0), (1, 0), (0, 1)]),
+            Behavior::StillLife { from: 1 }
+        );
+    }
+}
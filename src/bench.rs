@@ -0,0 +1,137 @@
+//! Timing each engine on a few standard workloads.
+
+use std::time::{Duration, Instant};
+
+use game_of_life::{
+    hashlife::HashLife,
+    life::Board,
+    patterns,
+    universe::{Dense, Edges, Sparse, Universe},
+};
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+// sides of the random soups
+const SOUP_SIZES: [u16; 3] = [64, 256, 512];
+// side of the empty field
+const FIELD_SIZE: u16 = 512;
+// share of living cells in the soups
+const SOUP_DENSITY: f64 = 0.5;
+
+/// The most generations a workload runs, keeping the dense field around the
+/// largest one within the biggest field.
+pub const MAX_GENERATIONS: u64 = 10_000;
+
+#[derive(Clone, Copy)]
+enum Engine {
+    // bit-packed rows of a finite field with dead edges
+    Dense,
+    // the living cells of the infinite plane
+    Sparse,
+    HashLife,
+}
+
+impl Engine {
+    const ALL: [Engine; 3] = [Engine::Dense, Engine::Sparse, Engine::HashLife];
+
+    fn name(self) -> &'static str {
+        match self {
+            Engine::Dense => "dense",
+            Engine::Sparse => "sparse",
+            Engine::HashLife => "hashlife",
+        }
+    }
+
+    fn universe(self, size: u16) -> Box<dyn Universe> {
+        match self {
+            Engine::Dense => Box::new(Dense::new(size, size)),
+            Engine::Sparse => Box::new(Sparse::default()),
+            Engine::HashLife => Box::new(HashLife::default()),
+        }
+    }
+}
+
+struct Workload {
+    name: String,
+    cells: Vec<Vec<bool>>,
+    // side of the square holding its cells
+    size: u16,
+}
+
+fn workloads(seed: u64) -> Vec<Workload> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let soups = SOUP_SIZES.map(|size| Workload {
+        name: format!("soup {size}x{size}"),
+        cells: (0..size)
+            .map(|_| (0..size).map(|_| rng.random_bool(SOUP_DENSITY)).collect())
+            .collect(),
+        size,
+    });
+    let gun = patterns::find("gosperglidergun").expect("the glider gun is built in");
+    let gun_cells = gun.cells();
+    let gun_size = gun_cells.len().max(gun_cells.first().map_or(0, Vec::len)) as u16;
+    soups
+        .into_iter()
+        .chain([
+            Workload {
+                name: gun.name.to_string(),
+                cells: gun_cells,
+                size: gun_size,
+            },
+            Workload {
+                name: format!("empty {FIELD_SIZE}x{FIELD_SIZE}"),
+                cells: Vec::new(),
+                size: FIELD_SIZE,
+            },
+        ])
+        .collect()
+}
+
+/// Runs `workload` on `engine` for `generations` generations and returns how
+/// long that took.
+fn time(workload: &Workload, engine: Engine, generations: u64) -> Duration {
+    // cells spread a cell a generation at most, so on a field this much wider
+    // on every side the dense engine computes the same generations as the
+    // infinite plane; nothing spreads out of an empty field
+    let margin = if workload.cells.iter().flatten().any(|&alive| alive) {
+        generations
+    } else {
+        0
+    };
+    let size = u16::try_from(u64::from(workload.size) + 2 * margin)
+        .expect("the generations keep the field within the biggest one");
+    let mut board = Board::new(size, size);
+    board.universe = engine.universe(size);
+    board.edges = Edges::Dead;
+    board.place(&workload.cells, (margin as i64, margin as i64));
+
+    let start = Instant::now();
+    match engine {
+        // jumping ahead is how HashLife is meant to run
+        Engine::HashLife => {
+            board.advance(generations);
+        }
+        Engine::Dense | Engine::Sparse => {
+            let mut spare = board.universe.clone_box();
+            for _ in 0..generations {
+                spare = board.step_into(spare);
+            }
+        }
+    }
+    start.elapsed()
+}
+
+/// Prints the generations per second of every engine on every workload, at
+/// most [`MAX_GENERATIONS`] of them.
+pub fn run(generations: u64, seed: u64) {
+    println!(
+        "{:<24} {:<10} {:>14}",
+        "workload", "engine", "generations/s"
+    );
+    for workload in workloads(seed) {
+        for engine in Engine::ALL {
+            let elapsed = time(&workload, engine, generations);
+            let rate = generations as f64 / elapsed.as_secs_f64();
+            println!("{:<24} {:<10} {:>14.0}", workload.name, engine.name(), rate);
+        }
+    }
+}
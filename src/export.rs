@@ -0,0 +1,149 @@
+//! Rendering the field to images.
+
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+    str::FromStr,
+};
+
+use anyhow::{Context, bail};
+use image::{Rgb, RgbImage};
+
+#[derive(Clone, Copy, Debug)]
+pub struct Colors {
+    pub alive: Rgb<u8>,
+    pub dead: Rgb<u8>,
+}
+
+/// A `#rrggbb` color given on the command line.
+#[derive(Clone, Copy, Debug)]
+pub struct HexColor(pub Rgb<u8>);
+
+impl FromStr for HexColor {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<HexColor> {
+        let hex = s.strip_prefix('#').unwrap_or(s);
+        if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            bail!("invalid color {s:?}: expected #rrggbb");
+        }
+        let channel = |index: usize| u8::from_str_radix(&hex[index..index + 2], 16);
+        Ok(HexColor(Rgb([channel(0)?, channel(2)?, channel(4)?])))
+    }
+}
+
+// the most pixels of an image, a few hundred megabytes of them
+const MAX_PIXELS: u64 = 1 << 26;
+
+/// Returns the size in pixels of the image of `width` x `height` cells, or
+/// an error if it would be too large.
+pub fn image_size(width: u64, height: u64, cell_size: u32) -> anyhow::Result<(u32, u32)> {
+    let pixels = |cells: u64| {
+        cells
+            .checked_mul(cell_size.into())
+            .and_then(|pixels| u32::try_from(pixels).ok())
+    };
+    match (pixels(width), pixels(height)) {
+        (Some(image_width), Some(image_height))
+            if u64::from(image_width) * u64::from(image_height) <= MAX_PIXELS =>
+        {
+            Ok((image_width, image_height))
+        }
+        _ => bail!(
+            "pattern too large for an image: {width}x{height} cells of {cell_size} pixels, \
+             at most {MAX_PIXELS} pixels"
+        ),
+    }
+}
+
+/// Draws every cell as a `cell_size` x `cell_size` square.
+pub fn render(field: &[Vec<bool>], cell_size: u32, colors: Colors) -> anyhow::Result<RgbImage> {
+    let (width, height) = image_size(
+        field.first().map_or(0, Vec::len) as u64,
+        field.len() as u64,
+        cell_size,
+    )?;
+    Ok(RgbImage::from_fn(width, height, |x, y| {
+        if field[(y / cell_size) as usize][(x / cell_size) as usize] {
+            colors.alive
+        } else {
+            colors.dead
+        }
+    }))
+}
+
+pub fn save_png(
+    field: &[Vec<bool>],
+    cell_size: u32,
+    colors: Colors,
+    path: &Path,
+) -> anyhow::Result<()> {
+    render(field, cell_size, colors)?
+        .save_with_format(path, image::ImageFormat::Png)
+        .with_context(|| format!("failed to write {}", path.display()))
+}
+
+/// Writes the field to a looping animated GIF, one frame per call to
+/// [`GifRecorder::add_frame`].
+pub struct GifRecorder {
+    encoder: gif::Encoder<BufWriter<File>>,
+    cell_size: u32,
+    // in hundredths of a second
+    delay: u16,
+}
+
+impl GifRecorder {
+    pub fn create(
+        path: &Path,
+        field_width: u16,
+        field_height: u16,
+        cell_size: u32,
+        colors: Colors,
+        fps: u16,
+    ) -> anyhow::Result<GifRecorder> {
+        let dimension = |cells: u16| {
+            u16::try_from(u32::from(cells) * cell_size)
+                .ok()
+                .with_context(|| {
+                    format!("a GIF cannot be wider or taller than {} pixels", u16::MAX)
+                })
+        };
+        let (width, height) = (dimension(field_width)?, dimension(field_height)?);
+        image_size(field_width.into(), field_height.into(), cell_size)?;
+
+        let file =
+            File::create(path).with_context(|| format!("failed to create {}", path.display()))?;
+        // palette index 0 is a dead cell, 1 a living one
+        let palette = [colors.dead.0, colors.alive.0].concat();
+        let mut encoder = gif::Encoder::new(BufWriter::new(file), width, height, &palette)?;
+        encoder.set_repeat(gif::Repeat::Infinite)?;
+        Ok(GifRecorder {
+            encoder,
+            cell_size,
+            delay: (100 / fps).max(1),
+        })
+    }
+
+    pub fn add_frame(&mut self, field: &[Vec<bool>]) -> anyhow::Result<()> {
+        let cell_size = self.cell_size;
+        let width = field.first().map_or(0, Vec::len) as u32 * cell_size;
+        let height = field.len() as u32 * cell_size;
+        let pixels: Vec<u8> = (0..height)
+            .flat_map(|y| {
+                let row = &field[(y / cell_size) as usize];
+                (0..width).map(move |x| u8::from(row[(x / cell_size) as usize]))
+            })
+            .collect();
+        let mut frame = gif::Frame::from_indexed_pixels(width as u16, height as u16, pixels, None);
+        frame.delay = self.delay;
+        self.encoder.write_frame(&frame)?;
+        Ok(())
+    }
+
+    /// Writes the GIF trailer and flushes the file.
+    pub fn finish(self) -> anyhow::Result<()> {
+        self.encoder.into_inner()?.flush()?;
+        Ok(())
+    }
+}
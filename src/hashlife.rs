@@ -0,0 +1,444 @@
+//! HashLife: the infinite plane as a quadtree of shared squares.
+//!
+//! Equal squares anywhere in the plane, and in any generation, are stored
+//! once, and the future of each square's center is computed once and
+//! remembered. Regular patterns like glider guns and breeders then run
+//! 2^n generations at a time in about the time of one.
+
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use crate::{
+    rule::Rule,
+    universe::{Edges, Player, Universe},
+};
+
+// index of a node in its store
+type Id = u32;
+
+// the two nodes of a single cell
+const DEAD: Id = 0;
+const ALIVE: Id = 1;
+
+// the smallest root, 8x8 cells
+const MIN_LEVEL: u8 = 3;
+// the largest root whose coordinates fit in an i64
+const MAX_LEVEL: u8 = 63;
+
+/// The most generations [`HashLife`] advances in one go, far fewer than it
+/// would take a pattern to outgrow the coordinates.
+pub const MAX_GENERATIONS: u64 = 1 << 56;
+
+// nodes beyond which a step first copies the current generation to a fresh
+// store, leaving the nodes only older generations use behind
+const NODE_LIMIT: usize = 1 << 22;
+
+#[derive(Clone, Copy)]
+struct Node {
+    // the north-west, north-east, south-west and south-east quadrants,
+    // unused for single cells
+    children: [Id; 4],
+    // the node is 2^level cells wide
+    level: u8,
+    population: u64,
+}
+
+struct Store {
+    nodes: Vec<Node>,
+    // the node with each four quadrants
+    ids: HashMap<[Id; 4], Id>,
+    // the center of a node 2^j generations on, by node and j
+    results: HashMap<(Id, u8), Id>,
+    // the empty node of each level
+    empty: Vec<Id>,
+    // the rule and neighborhood the results follow
+    rule: Rule,
+    neighborhood: Vec<(i64, i64)>,
+}
+
+impl Store {
+    fn new() -> Store {
+        let cell = |population| Node {
+            children: [DEAD; 4],
+            level: 0,
+            population,
+        };
+        Store {
+            nodes: vec![cell(0), cell(1)],
+            ids: HashMap::new(),
+            results: HashMap::new(),
+            empty: vec![DEAD],
+            rule: Rule::CONWAY,
+            neighborhood: Vec::new(),
+        }
+    }
+
+    fn node(&self, id: Id) -> Node {
+        self.nodes[id as usize]
+    }
+
+    /// Returns the node made of `children`, creating it unless it exists.
+    fn join(&mut self, children: [Id; 4]) -> Id {
+        if let Some(&id) = self.ids.get(&children) {
+            return id;
+        }
+        let id = self.nodes.len() as Id;
+        self.nodes.push(Node {
+            children,
+            level: self.node(children[0]).level + 1,
+            population: children
+                .iter()
+                .map(|&child| self.node(child).population)
+                .sum(),
+        });
+        self.ids.insert(children, id);
+        id
+    }
+
+    fn empty(&mut self, level: u8) -> Id {
+        while self.empty.len() <= level as usize {
+            let empty = *self.empty.last().unwrap();
+            let joined = self.join([empty; 4]);
+            self.empty.push(joined);
+        }
+        self.empty[level as usize]
+    }
+
+    /// Returns the square half as wide in the middle of `id`.
+    fn center(&mut self, id: Id) -> Id {
+        let [nw, ne, sw, se] = self.node(id).children;
+        self.join([
+            self.node(nw).children[3],
+            self.node(ne).children[2],
+            self.node(sw).children[1],
+            self.node(se).children[0],
+        ])
+    }
+
+    /// Surrounds `id` with dead cells, doubling its width around the same
+    /// center.
+    fn expand(&mut self, id: Id) -> Id {
+        let Node {
+            children: [nw, ne, sw, se],
+            level,
+            ..
+        } = self.node(id);
+        assert!(level < MAX_LEVEL, "the pattern outgrew the coordinates");
+        let empty = self.empty(level - 1);
+        let children = [
+            self.join([empty, empty, empty, nw]),
+            self.join([empty, empty, ne, empty]),
+            self.join([empty, sw, empty, empty]),
+            self.join([se, empty, empty, empty]),
+        ];
+        self.join(children)
+    }
+
+    fn get(&self, mut id: Id, mut x: u64, mut y: u64) -> bool {
+        loop {
+            let node = self.node(id);
+            if node.population == 0 {
+                return false;
+            }
+            if node.level == 0 {
+                return true;
+            }
+            let half = 1 << (node.level - 1);
+            id = node.children[usize::from(half <= y) * 2 + usize::from(half <= x)];
+            x %= half;
+            y %= half;
+        }
+    }
+
+    fn set(&mut self, id: Id, x: u64, y: u64, alive: bool) -> Id {
+        let node = self.node(id);
+        if node.level == 0 {
+            return if alive { ALIVE } else { DEAD };
+        }
+        let half = 1 << (node.level - 1);
+        let quadrant = usize::from(half <= y) * 2 + usize::from(half <= x);
+        let mut children = node.children;
+        children[quadrant] = self.set(children[quadrant], x % half, y % half, alive);
+        self.join(children)
+    }
+
+    fn live_cells(&self, id: Id, (x, y): (i64, i64), cells: &mut Vec<(i64, i64)>) {
+        let node = self.node(id);
+        if node.population == 0 {
+            return;
+        }
+        if node.level == 0 {
+            cells.push((x, y));
+            return;
+        }
+        let half = 1 << (node.level - 1);
+        for (quadrant, &child) in node.children.iter().enumerate() {
+            let (dx, dy) = ((quadrant % 2) as i64, (quadrant / 2) as i64);
+            self.live_cells(child, (x + dx * half, y + dy * half), cells);
+        }
+    }
+
+    /// Forgets the results when the rule or neighborhood changes.
+    fn follow(&mut self, rule: &Rule, neighborhood: &[(i64, i64)]) {
+        if self.rule != *rule || self.neighborhood != neighborhood {
+            self.rule = *rule;
+            self.neighborhood = neighborhood.to_vec();
+            self.results.clear();
+        }
+    }
+
+    /// Returns the 2x2 center of the 4x4 node `id` one generation on.
+    fn small_result(&mut self, id: Id) -> Id {
+        let mut cells = [[false; 4]; 4];
+        for (y, row) in cells.iter_mut().enumerate() {
+            for (x, cell) in row.iter_mut().enumerate() {
+                *cell = self.get(id, x as u64, y as u64);
+            }
+        }
+        let next = |x: usize, y: usize| {
+            let live_neighbors = self
+                .neighborhood
+                .iter()
+                .filter(|&&(dx, dy)| cells[(y as i64 + dy) as usize][(x as i64 + dx) as usize])
+                .count();
+            if self.rule.next_state(cells[y][x], live_neighbors) {
+                ALIVE
+            } else {
+                DEAD
+            }
+        };
+        let children = [next(1, 1), next(2, 1), next(1, 2), next(2, 2)];
+        self.join(children)
+    }
+
+    /// Returns the center of `id`, half as wide, 2^j generations on, for a
+    /// `j` up to the node's level less 2: cells move at most a cell a
+    /// generation, so nothing beyond the node can reach its center sooner.
+    fn result(&mut self, id: Id, j: u8) -> Id {
+        let node = self.node(id);
+        if node.population == 0 {
+            return self.empty(node.level - 1);
+        }
+        if let Some(&result) = self.results.get(&(id, j)) {
+            return result;
+        }
+
+        let result = if node.level == 2 {
+            self.small_result(id)
+        } else {
+            let [nw, ne, sw, se] = node.children.map(|child| self.node(child).children);
+            // the 3x3 overlapping squares half as wide, row by row
+            let squares = [
+                node.children[0],
+                self.join([nw[1], ne[0], nw[3], ne[2]]),
+                node.children[1],
+                self.join([nw[2], nw[3], sw[0], sw[1]]),
+                self.join([nw[3], ne[2], sw[1], se[0]]),
+                self.join([ne[2], ne[3], se[0], se[1]]),
+                node.children[2],
+                self.join([sw[1], se[0], sw[3], se[2]]),
+                node.children[3],
+            ];
+            // at full speed both halves of the way advance, otherwise only
+            // the second one does
+            let full = j == node.level - 2;
+            let mut inner = [DEAD; 9];
+            for (inner, square) in inner.iter_mut().zip(squares) {
+                *inner = if full {
+                    self.result(square, j - 1)
+                } else {
+                    self.center(square)
+                };
+            }
+            let second = j.min(node.level - 3);
+            let quadrants = [
+                [inner[0], inner[1], inner[3], inner[4]],
+                [inner[1], inner[2], inner[4], inner[5]],
+                [inner[3], inner[4], inner[6], inner[7]],
+                [inner[4], inner[5], inner[7], inner[8]],
+            ];
+            let mut children = [DEAD; 4];
+            for (child, quadrant) in children.iter_mut().zip(quadrants) {
+                let joined = self.join(quadrant);
+                *child = self.result(joined, second);
+            }
+            self.join(children)
+        };
+        self.results.insert((id, j), result);
+        result
+    }
+
+    /// Advances the root `id` 2^j generations, returning a root as small as
+    /// still holds the pattern.
+    fn advance(&mut self, mut id: Id, j: u8) -> Id {
+        // the pattern within the middle half, leaving room for it to grow
+        while self.node(id).level < j + 2 || !self.centered(id) {
+            id = self.expand(id);
+        }
+        id = self.expand(id);
+        id = self.result(id, j);
+        while MIN_LEVEL < self.node(id).level && self.centered(id) {
+            id = self.center(id);
+        }
+        id
+    }
+
+    fn centered(&mut self, id: Id) -> bool {
+        let center = self.center(id);
+        self.node(center).population == self.node(id).population
+    }
+
+    /// Copies `id` into `store`, with `copies` of the nodes copied so far.
+    fn copy(&self, id: Id, store: &mut Store, copies: &mut HashMap<Id, Id>) -> Id {
+        if id == DEAD || id == ALIVE {
+            return id;
+        }
+        if let Some(&copy) = copies.get(&id) {
+            return copy;
+        }
+        let mut children = self.node(id).children;
+        for child in &mut children {
+            *child = self.copy(*child, store, copies);
+        }
+        let copy = store.join(children);
+        copies.insert(id, copy);
+        copy
+    }
+}
+
+/// The infinite plane stepped by HashLife.
+///
+/// Snapshots share their store, so cloning one is cheap. HashLife keeps no
+/// ages and no sides: living cells are always a generation old and on side
+/// `A`, so it cannot play the Immigration variant.
+#[derive(Clone)]
+pub struct HashLife {
+    store: Rc<RefCell<Store>>,
+    // centered on (0, 0)
+    root: Id,
+}
+
+impl Default for HashLife {
+    fn default() -> HashLife {
+        let mut store = Store::new();
+        let root = store.empty(MIN_LEVEL);
+        HashLife {
+            store: Rc::new(RefCell::new(store)),
+            root,
+        }
+    }
+}
+
+impl HashLife {
+    /// Returns `(x, y)` from the north-west corner of the root, if it lies
+    /// within it.
+    fn offset(&self, x: i64, y: i64) -> Option<(u64, u64)> {
+        let half = 1 << (self.store.borrow().node(self.root).level - 1);
+        let inside = |coordinate: i64| (-half..half).contains(&coordinate);
+        (inside(x) && inside(y)).then(|| (x.abs_diff(-half), y.abs_diff(-half)))
+    }
+}
+
+impl Universe for HashLife {
+    fn is_alive(&self, x: i64, y: i64) -> bool {
+        self.offset(x, y)
+            .is_some_and(|(x, y)| self.store.borrow().get(self.root, x, y))
+    }
+
+    fn set_player(&mut self, x: i64, y: i64, _player: Player) {
+        let mut store = self.store.borrow_mut();
+        let mut root = self.root;
+        let mut half: i64 = 1 << (store.node(root).level - 1);
+        while !(-half..half).contains(&x) || !(-half..half).contains(&y) {
+            if store.node(root).level == MAX_LEVEL {
+                return;
+            }
+            root = store.expand(root);
+            half *= 2;
+        }
+        self.root = store.set(root, x.abs_diff(-half), y.abs_diff(-half), true);
+    }
+
+    fn kill(&mut self, x: i64, y: i64) {
+        if let Some((x, y)) = self.offset(x, y) {
+            let root = self.store.borrow_mut().set(self.root, x, y, false);
+            self.root = root;
+        }
+    }
+
+    fn age(&self, x: i64, y: i64) -> u32 {
+        u32::from(self.is_alive(x, y))
+    }
+
+    fn player(&self, _x: i64, _y: i64) -> Player {
+        Player::A
+    }
+
+    /// Only living cells and their neighbors can be alive in the next
+    /// generation, so `rule` must not give birth to cells without neighbors,
+    /// and the `neighborhood` must lie within a cell of each cell.
+    fn next(&self, rule: &Rule, edges: Edges, neighborhood: &[(i64, i64)]) -> Box<dyn Universe> {
+        self.advance(rule, edges, neighborhood, 1)
+    }
+
+    /// Advances the generations a power of 2 at a time, up to
+    /// [`MAX_GENERATIONS`] of them.
+    fn advance(
+        &self,
+        rule: &Rule,
+        _edges: Edges,
+        neighborhood: &[(i64, i64)],
+        generations: u64,
+    ) -> Box<dyn Universe> {
+        assert!(
+            generations <= MAX_GENERATIONS,
+            "HashLife advances at most {MAX_GENERATIONS} generations at once"
+        );
+        let mut next = self.clone();
+        if NODE_LIMIT < self.store.borrow().nodes.len() {
+            let mut store = Store::new();
+            next.root = self
+                .store
+                .borrow()
+                .copy(self.root, &mut store, &mut HashMap::new());
+            next.store = Rc::new(RefCell::new(store));
+        }
+
+        let mut store = next.store.borrow_mut();
+        store.follow(rule, neighborhood);
+        let mut root = next.root;
+        for j in 0..u64::BITS as u8 {
+            if generations & 1 << j != 0 {
+                root = store.advance(root, j);
+            }
+        }
+        drop(store);
+        next.root = root;
+        Box::new(next)
+    }
+
+    fn live_cells(&self) -> Vec<(i64, i64)> {
+        let store = self.store.borrow();
+        let half = 1 << (store.node(self.root).level - 1);
+        let mut cells = Vec::new();
+        store.live_cells(self.root, (-half, -half), &mut cells);
+        cells
+    }
+
+    fn clear(&mut self) {
+        self.root = self.store.borrow_mut().empty(MIN_LEVEL);
+    }
+
+    fn bounds(&self) -> Option<(u16, u16)> {
+        None
+    }
+
+    fn clone_box(&self) -> Box<dyn Universe> {
+        Box::new(self.clone())
+    }
+
+    fn resize(&mut self, _width: u16, _height: u16) {}
+
+    fn population(&self) -> usize {
+        self.store.borrow().node(self.root).population as usize
+    }
+}
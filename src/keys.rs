@@ -0,0 +1,449 @@
+//! Key bindings, configurable through `~/.config/game-of-life/keys.toml`.
+//!
+//! The file maps action names to a key or a list of keys:
+//!
+//! ```toml
+//! quit = "q"
+//! faster = ["+", "."]
+//! left = "none"
+//! ```
+//!
+//! A key is a single character or one of `space`, `enter`, `esc`, `tab`,
+//! `backtab` (shift-tab), `backspace`, `pageup`, `pagedown`, `home`, `end`,
+//! optionally prefixed with `ctrl-`. `none` unbinds the action.
+//! Actions missing from the file keep their default keys, and the arrow keys
+//! always move the cursor, or with Shift pan a screen.
+
+use std::{collections::HashMap, fmt, path::PathBuf, str::FromStr};
+
+use anyhow::{Context, bail};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Quit,
+    Stop,
+    ToggleView,
+    ToggleHeader,
+    ToggleGrid,
+    ToggleAge,
+    ToggleHeatmap,
+    ToggleDiff,
+    Analyze,
+    ToggleCell,
+    Pen,
+    Eraser,
+    Step,
+    StepBack,
+    RunFor,
+    FastForward,
+    Clear,
+    Randomize,
+    ToggleWrap,
+    Undo,
+    Redo,
+    PlacePattern,
+    Save,
+    SavePlaintext,
+    SavePng,
+    SaveSession,
+    LoadSession,
+    NextBoard,
+    PreviousBoard,
+    NewBoard,
+    Select,
+    Paste,
+    Prompt,
+    Faster,
+    Slower,
+    Warp,
+    Unwarp,
+    Center,
+    RowStart,
+    RowEnd,
+    Redraw,
+    PanUp,
+    PanDown,
+    PanLeft,
+    PanRight,
+    Help,
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Action {
+    pub const ALL: [Action; 50] = [
+        Action::Quit,
+        Action::Stop,
+        Action::ToggleView,
+        Action::ToggleHeader,
+        Action::ToggleGrid,
+        Action::ToggleAge,
+        Action::ToggleHeatmap,
+        Action::ToggleDiff,
+        Action::Analyze,
+        Action::ToggleCell,
+        Action::Pen,
+        Action::Eraser,
+        Action::Step,
+        Action::StepBack,
+        Action::RunFor,
+        Action::FastForward,
+        Action::Clear,
+        Action::Randomize,
+        Action::ToggleWrap,
+        Action::Undo,
+        Action::Redo,
+        Action::PlacePattern,
+        Action::Save,
+        Action::SavePlaintext,
+        Action::SavePng,
+        Action::SaveSession,
+        Action::LoadSession,
+        Action::NextBoard,
+        Action::PreviousBoard,
+        Action::NewBoard,
+        Action::Select,
+        Action::Paste,
+        Action::Prompt,
+        Action::Faster,
+        Action::Slower,
+        Action::Warp,
+        Action::Unwarp,
+        Action::Center,
+        Action::RowStart,
+        Action::RowEnd,
+        Action::Redraw,
+        Action::PanUp,
+        Action::PanDown,
+        Action::PanLeft,
+        Action::PanRight,
+        Action::Help,
+        Action::Up,
+        Action::Down,
+        Action::Left,
+        Action::Right,
+    ];
+
+    fn default_keys(self) -> &'static [&'static str] {
+        match self {
+            Action::Quit => &["q"],
+            Action::Stop => &["s"],
+            Action::ToggleView => &["v"],
+            Action::ToggleHeader => &["H"],
+            Action::ToggleGrid => &["#"],
+            Action::ToggleAge => &["a"],
+            Action::ToggleHeatmap => &["m"],
+            Action::ToggleDiff => &["d"],
+            Action::Analyze => &["A"],
+            Action::ToggleCell => &["space"],
+            Action::Pen => &["i"],
+            Action::Eraser => &["e"],
+            Action::Step => &["n"],
+            Action::StepBack => &["b"],
+            Action::RunFor => &["T"],
+            Action::FastForward => &[">"],
+            Action::Clear => &["c"],
+            Action::Randomize => &["r"],
+            Action::ToggleWrap => &["t"],
+            Action::Undo => &["u"],
+            Action::Redo => &["ctrl-r"],
+            Action::PlacePattern => &["p"],
+            Action::Save => &["w"],
+            Action::SavePlaintext => &["W"],
+            Action::SavePng => &["ctrl-p"],
+            Action::SaveSession => &["S"],
+            Action::LoadSession => &["L"],
+            Action::NextBoard => &["tab"],
+            Action::PreviousBoard => &["backtab"],
+            Action::NewBoard => &["N"],
+            Action::Select => &["V"],
+            Action::Paste => &["P"],
+            Action::Prompt => &[":"],
+            Action::Faster => &["+", "."],
+            Action::Slower => &["-", ","],
+            Action::Warp => &["*"],
+            Action::Unwarp => &["/"],
+            Action::Center => &["g"],
+            Action::RowStart => &["0"],
+            Action::RowEnd => &["$"],
+            Action::Redraw => &["ctrl-l"],
+            Action::PanUp => &["pageup"],
+            Action::PanDown => &["pagedown"],
+            Action::PanLeft => &["home"],
+            Action::PanRight => &["end"],
+            Action::Help => &["?"],
+            Action::Up => &["k"],
+            Action::Down => &["j"],
+            Action::Left => &["h"],
+            Action::Right => &["l"],
+        }
+    }
+
+    /// Describes the action for the list of keys, `None` for cursor movement.
+    pub fn description(self) -> Option<&'static str> {
+        Some(match self {
+            Action::Quit => "quit",
+            Action::Stop => "stop",
+            Action::ToggleView => "toggle view",
+            Action::ToggleHeader => "toggle the header",
+            Action::ToggleGrid => "toggle grid lines",
+            Action::ToggleAge => "toggle coloring cells by age",
+            Action::ToggleHeatmap => "toggle a heatmap of recent births and deaths",
+            Action::ToggleDiff => "toggle coloring the last step's births and deaths",
+            Action::Analyze => "tell a still life, oscillator or spaceship apart",
+            Action::ToggleCell => "toggle cell",
+            Action::Pen => "draw while moving",
+            Action::Eraser => "erase while moving",
+            Action::Step => "step",
+            Action::StepBack => "step back",
+            Action::RunFor => "run to a target generation",
+            Action::FastForward => "fast-forward",
+            Action::Clear => "clear",
+            Action::Randomize => "randomize",
+            Action::ToggleWrap => "cycle the edges: torus, bounded, mirror",
+            Action::Undo => "undo",
+            Action::Redo => "redo",
+            Action::PlacePattern => "place a pattern",
+            Action::Save => "save",
+            Action::SavePlaintext => "save as plaintext",
+            Action::SavePng => "save an image of the whole field",
+            Action::SaveSession => "save the session",
+            Action::LoadSession => "load the session",
+            Action::NextBoard => "switch to the next board",
+            Action::PreviousBoard => "switch to the previous board",
+            Action::NewBoard => "open a new board",
+            Action::Select => "select",
+            Action::Paste => "paste",
+            Action::Prompt => "go to a cell",
+            Action::Faster => "speed up",
+            Action::Slower => "slow down",
+            Action::Warp => "double the generations per step (warp)",
+            Action::Unwarp => "halve the generations per step",
+            Action::Center => "center the cursor",
+            Action::RowStart => "jump to the row start",
+            Action::RowEnd => "jump to the row end",
+            Action::Redraw => "redraw the whole screen",
+            Action::PanUp => "pan a screen up, also Shift-Up",
+            Action::PanDown => "pan a screen down, also Shift-Down",
+            Action::PanLeft => "pan a screen left, also Shift-Left",
+            Action::PanRight => "pan a screen right, also Shift-Right",
+            Action::Help => "list every key",
+            Action::Up | Action::Down | Action::Left | Action::Right => return None,
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Key {
+    code: KeyCode,
+    ctrl: bool,
+}
+
+impl Key {
+    fn from_event(key_event: &KeyEvent) -> Key {
+        Key {
+            code: key_event.code,
+            ctrl: key_event.modifiers.contains(KeyModifiers::CONTROL),
+        }
+    }
+}
+
+impl FromStr for Key {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Key> {
+        let (ctrl, name) = match s.strip_prefix("ctrl-") {
+            Some(name) if !name.is_empty() => (true, name),
+            _ => (false, s),
+        };
+        let code = match name {
+            "space" => KeyCode::Char(' '),
+            "enter" => KeyCode::Enter,
+            "esc" => KeyCode::Esc,
+            "tab" => KeyCode::Tab,
+            "backtab" => KeyCode::BackTab,
+            "backspace" => KeyCode::Backspace,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            _ => {
+                let mut chars = name.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => KeyCode::Char(c),
+                    _ => bail!("unknown key {s:?}"),
+                }
+            }
+        };
+        Ok(Key { code, ctrl })
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.ctrl {
+            write!(f, "Ctrl-")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char(c) => write!(f, "{c}"),
+            KeyCode::Enter => write!(f, "enter"),
+            KeyCode::Esc => write!(f, "esc"),
+            KeyCode::Tab => write!(f, "tab"),
+            KeyCode::BackTab => write!(f, "backtab"),
+            KeyCode::Backspace => write!(f, "backspace"),
+            KeyCode::PageUp => write!(f, "pageup"),
+            KeyCode::PageDown => write!(f, "pagedown"),
+            KeyCode::Home => write!(f, "home"),
+            KeyCode::End => write!(f, "end"),
+            code => write!(f, "{code:?}"),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum KeyList {
+    One(String),
+    Many(Vec<String>),
+}
+
+pub struct KeyBindings {
+    actions: HashMap<Key, Action>,
+    keys: HashMap<Action, Vec<Key>>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        KeyBindings::from_toml("").expect("default key bindings are valid")
+    }
+}
+
+impl KeyBindings {
+    /// Loads the bindings from the config file, falling back to the defaults
+    /// when it does not exist.
+    pub fn load() -> anyhow::Result<KeyBindings> {
+        let Some(path) = config_path() else {
+            return Ok(KeyBindings::default());
+        };
+        match std::fs::read_to_string(&path) {
+            Ok(input) => KeyBindings::from_toml(&input)
+                .with_context(|| format!("invalid key bindings in {}", path.display())),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+                Ok(KeyBindings::default())
+            }
+            Err(error) => Err(error).with_context(|| format!("failed to read {}", path.display())),
+        }
+    }
+
+    pub fn from_toml(input: &str) -> anyhow::Result<KeyBindings> {
+        let config: HashMap<Action, KeyList> = toml::from_str(input)?;
+
+        let mut keys = HashMap::new();
+        for (&action, list) in &config {
+            let names = match list {
+                KeyList::One(name) => std::slice::from_ref(name),
+                KeyList::Many(names) => names.as_slice(),
+            };
+            let parsed = names
+                .iter()
+                .filter(|name| *name != "none")
+                .map(|name| name.parse())
+                .collect::<anyhow::Result<Vec<Key>>>()?;
+            keys.insert(action, parsed);
+        }
+
+        let mut actions = HashMap::new();
+        for (&action, action_keys) in &keys {
+            for &key in action_keys {
+                if let Some(other) = actions.insert(key, action) {
+                    bail!("{key} is bound to both {other:?} and {action:?}");
+                }
+            }
+        }
+
+        // keys taken by the config file are no longer available to the defaults
+        for action in Action::ALL {
+            if keys.contains_key(&action) {
+                continue;
+            }
+            let defaults: Vec<Key> = action
+                .default_keys()
+                .iter()
+                .map(|name| name.parse().expect("default keys are valid"))
+                .filter(|key| !actions.contains_key(key))
+                .collect();
+            for &key in &defaults {
+                actions.insert(key, action);
+            }
+            keys.insert(action, defaults);
+        }
+
+        Ok(KeyBindings { actions, keys })
+    }
+
+    pub fn action(&self, key_event: &KeyEvent) -> Option<Action> {
+        let shift = key_event.modifiers.contains(KeyModifiers::SHIFT);
+        match (key_event.code, shift) {
+            (KeyCode::Up, false) => Some(Action::Up),
+            (KeyCode::Down, false) => Some(Action::Down),
+            (KeyCode::Left, false) => Some(Action::Left),
+            (KeyCode::Right, false) => Some(Action::Right),
+            (KeyCode::Up, true) => Some(Action::PanUp),
+            (KeyCode::Down, true) => Some(Action::PanDown),
+            (KeyCode::Left, true) => Some(Action::PanLeft),
+            (KeyCode::Right, true) => Some(Action::PanRight),
+            _ => self.actions.get(&Key::from_event(key_event)).copied(),
+        }
+    }
+
+    /// Returns the short help line pointing to the list of keys.
+    pub fn hint(&self) -> String {
+        let hints: Vec<String> = [Action::Help, Action::Quit]
+            .into_iter()
+            .filter_map(|action| {
+                let description = action.description()?;
+                let key = self.keys.get(&action)?.first()?;
+                Some(format!("'{key}' to {description}"))
+            })
+            .collect();
+        format!("Press {}", hints.join(", "))
+    }
+
+    /// Returns a line per bound action, its keys aligned in a column before
+    /// the description.
+    pub fn help(&self) -> Vec<String> {
+        let mut entries: Vec<(String, &str)> = Action::ALL
+            .iter()
+            .filter_map(|&action| {
+                let description = action.description()?;
+                let keys = self.keys.get(&action).filter(|keys| !keys.is_empty())?;
+                let names: Vec<String> = keys.iter().map(Key::to_string).collect();
+                Some((names.join(" "), description))
+            })
+            .collect();
+        entries.push(("arrows".to_string(), "move the cursor"));
+
+        let width = entries
+            .iter()
+            .map(|(names, _)| names.chars().count())
+            .max()
+            .unwrap_or(0);
+        entries
+            .into_iter()
+            .map(|(names, description)| format!("{names:>width$}  {description}"))
+            .collect()
+    }
+}
+
+fn config_path() -> Option<PathBuf> {
+    let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config_dir.join("game-of-life").join("keys.toml"))
+}
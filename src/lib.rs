@@ -0,0 +1,26 @@
+//! The rules, engines and pattern formats of the game, independent of the
+//! terminal.
+
+pub mod analysis;
+pub mod hashlife;
+pub mod life;
+pub mod life106;
+pub mod patterns;
+pub mod plaintext;
+pub mod rle;
+pub mod rule;
+pub mod universe;
+
+use rule::Rule;
+use universe::{Dense, Edges, Neighborhood};
+
+/// Computes the generation after `field` in the Moore neighborhood, `wrap`
+/// making the field a torus rather than surrounding it with dead cells.
+/// Each call allocates a new field; a [`universe::DoubleBuffer`] steps
+/// without allocating.
+pub fn next_generation(field: &[Vec<bool>], rule: &Rule, wrap: bool) -> Vec<Vec<bool>> {
+    let edges = if wrap { Edges::Wrap } else { Edges::Dead };
+    Dense::from_cells(field)
+        .step(rule, edges, &Neighborhood::Moore.offsets())
+        .into_cells()
+}
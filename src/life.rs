@@ -0,0 +1,311 @@
+//! The board of the game: the cells together with the rules that step them.
+
+use crate::{
+    rule::Rule,
+    universe::{Dense, Edges, Neighborhood, Universe},
+};
+
+pub struct Board {
+    pub universe: Box<dyn Universe>,
+    pub rule: Rule,
+    // offsets of the cells counted as neighbors
+    pub neighborhood: Vec<(i64, i64)>,
+    // what lies beyond the edges of a finite universe
+    pub edges: Edges,
+}
+
+impl Board {
+    /// Creates an empty toroidal board playing Conway's rule.
+    pub fn new(width: u16, height: u16) -> Board {
+        Board {
+            universe: Box::new(Dense::new(width, height)),
+            rule: Rule::CONWAY,
+            neighborhood: Neighborhood::Moore.offsets(),
+            edges: Edges::Wrap,
+        }
+    }
+
+    pub fn get(&self, x: i64, y: i64) -> bool {
+        self.universe.is_alive(x, y)
+    }
+
+    pub fn set(&mut self, x: i64, y: i64, alive: bool) {
+        self.universe.set(x, y, alive);
+    }
+
+    pub fn alive_count(&self) -> usize {
+        self.universe.population()
+    }
+
+    /// Copies a pattern with its top-left corner at `(x, y)`, dead cells
+    /// included. A torus wraps the parts past an edge to the other side,
+    /// otherwise they are cut off.
+    pub fn place(&mut self, pattern: &[Vec<bool>], (x, y): (i64, i64)) {
+        for (dy, row) in pattern.iter().enumerate() {
+            for (dx, &alive) in row.iter().enumerate() {
+                let (mut target_x, mut target_y) = (x + dx as i64, y + dy as i64);
+                if let Some((width, height)) = self.universe.bounds()
+                    && self.edges == Edges::Wrap
+                {
+                    target_x = target_x.rem_euclid(width as i64);
+                    target_y = target_y.rem_euclid(height as i64);
+                }
+                self.set(target_x, target_y, alive);
+            }
+        }
+    }
+
+    /// Advances the board one generation and returns the previous one.
+    pub fn step(&mut self) -> Box<dyn Universe> {
+        let next = self
+            .universe
+            .next(&self.rule, self.edges, &self.neighborhood);
+        std::mem::replace(&mut self.universe, next)
+    }
+
+    /// Advances the board one generation like [`Board::step`], into the
+    /// storage of `spare`, a generation no longer needed.
+    pub fn step_into(&mut self, spare: Box<dyn Universe>) -> Box<dyn Universe> {
+        let next = self
+            .universe
+            .next_into(&self.rule, self.edges, &self.neighborhood, spare);
+        std::mem::replace(&mut self.universe, next)
+    }
+
+    /// Advances the board `generations` generations and returns the
+    /// previous one.
+    pub fn advance(&mut self, generations: u64) -> Box<dyn Universe> {
+        let next = self
+            .universe
+            .advance(&self.rule, self.edges, &self.neighborhood, generations);
+        std::mem::replace(&mut self.universe, next)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{Rng, SeedableRng, rngs::StdRng};
+
+    use super::*;
+    use crate::{
+        hashlife::HashLife,
+        universe::{DoubleBuffer, Sparse},
+    };
+
+    fn board_with(width: u16, height: u16, cells: &[(i64, i64)]) -> Board {
+        let mut board = Board::new(width, height);
+        for &(x, y) in cells {
+            board.set(x, y, true);
+        }
+        board
+    }
+
+    fn live_cells(board: &Board) -> Vec<(i64, i64)> {
+        let mut cells = board.universe.live_cells();
+        cells.sort_unstable();
+        cells
+    }
+
+    #[test]
+    fn blinker_has_period_2() {
+        let horizontal = [(1, 2), (2, 2), (3, 2)];
+        let vertical = [(2, 1), (2, 2), (2, 3)];
+        let mut board = board_with(5, 5, &horizontal);
+
+        board.step();
+        assert_eq!(live_cells(&board), vertical);
+        assert!(!board.get(1, 2));
+        board.step();
+        assert_eq!(live_cells(&board), horizontal);
+    }
+
+    #[test]
+    fn block_stays_still() {
+        let block = [(1, 1), (1, 2), (2, 1), (2, 2)];
+        let mut board = board_with(4, 4, &block);
+
+        for _ in 0..3 {
+            board.step();
+            assert_eq!(live_cells(&board), block);
+        }
+        assert_eq!(board.alive_count(), 4);
+    }
+
+    #[test]
+    fn glider_moves_diagonally_on_a_torus() {
+        let glider = [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)];
+        // starts next to the corner so the glider crosses both edges
+        let (width, height) = (6, 6);
+        let offset = (4, 4);
+        let shifted = |(dx, dy): (i64, i64)| {
+            let mut cells: Vec<(i64, i64)> = glider
+                .iter()
+                .map(|&(x, y)| ((x + dx).rem_euclid(width), (y + dy).rem_euclid(height)))
+                .collect();
+            cells.sort_unstable();
+            cells
+        };
+
+        let mut board = Board::new(width as u16, height as u16);
+        for (x, y) in shifted(offset) {
+            board.set(x, y, true);
+        }
+        for _ in 0..4 {
+            board.step();
+        }
+        assert_eq!(live_cells(&board), shifted((offset.0 + 1, offset.1 + 1)));
+    }
+
+    #[test]
+    fn word_and_cell_steps_agree() {
+        // the same neighbors in another order, which only the cell by cell
+        // step handles
+        let mut reversed = Neighborhood::Moore.offsets();
+        reversed.reverse();
+        let mut rng = StdRng::seed_from_u64(1);
+
+        for edges in [Edges::Wrap, Edges::Dead, Edges::Mirror] {
+            for rule in ["B3/S23", "B36/S125", "B0/S8"] {
+                let mut words = Board::new(70, 9);
+                words.rule = rule.parse().expect("the rule is valid");
+                words.edges = edges;
+                for y in 0..9 {
+                    for x in 0..70 {
+                        words.set(x, y, rng.random_bool(0.4));
+                    }
+                }
+                let mut cells = Board {
+                    universe: words.universe.clone_box(),
+                    rule: words.rule,
+                    neighborhood: reversed.clone(),
+                    edges,
+                };
+
+                for _ in 0..10 {
+                    words.step();
+                    cells.step();
+                    assert_eq!(live_cells(&words), live_cells(&cells));
+                    for (x, y) in live_cells(&words) {
+                        assert_eq!(words.universe.age(x, y), cells.universe.age(x, y));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn double_buffered_steps_reuse_their_buffers() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let field: Vec<Vec<bool>> = (0..20)
+            .map(|_| (0..100).map(|_| rng.random_bool(0.4)).collect())
+            .collect();
+        let rows = |grid: &Dense| {
+            grid.rows()
+                .iter()
+                .map(|row| row.as_ptr())
+                .collect::<Vec<_>>()
+        };
+
+        for neighborhood in [Neighborhood::Moore, Neighborhood::VonNeumann] {
+            for edges in [Edges::Wrap, Edges::Dead, Edges::Mirror] {
+                let neighborhood = neighborhood.offsets();
+                let mut buffers = DoubleBuffer::new(Dense::from_cells(&field));
+                let (front, back) = (rows(buffers.front()), rows(buffers.back()));
+
+                for generation in 0..6 {
+                    let expected = buffers.front().step(&Rule::CONWAY, edges, &neighborhood);
+                    buffers.step(&Rule::CONWAY, edges, &neighborhood);
+                    assert_eq!(buffers.front().rows(), expected.rows());
+                    // none of the stale ages of the back buffer are left over
+                    for (y, x) in (0..20).flat_map(|y| (0..100).map(move |x| (y, x))) {
+                        assert_eq!(buffers.front().age(x, y), expected.age(x, y));
+                    }
+                    let (now_front, now_back) = if generation % 2 == 0 {
+                        (&back, &front)
+                    } else {
+                        (&front, &back)
+                    };
+                    assert_eq!(&rows(buffers.front()), now_front);
+                    assert_eq!(&rows(buffers.back()), now_back);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn hashlife_agrees_with_the_sparse_plane() {
+        let mut rng = StdRng::seed_from_u64(2);
+
+        for neighborhood in [Neighborhood::Moore, Neighborhood::VonNeumann] {
+            for rule in ["B3/S23", "B36/S23", "B2/S"] {
+                let mut sparse = Board::new(1, 1);
+                sparse.universe = Box::new(Sparse::default());
+                sparse.rule = rule.parse().expect("the rule is valid");
+                sparse.neighborhood = neighborhood.offsets();
+                for y in -10..10 {
+                    for x in -10..10 {
+                        sparse.set(x, y, rng.random_bool(0.4));
+                    }
+                }
+                let mut hashlife = Board {
+                    universe: Box::new(HashLife::default()),
+                    rule: sparse.rule,
+                    neighborhood: sparse.neighborhood.clone(),
+                    edges: sparse.edges,
+                };
+                for (x, y) in sparse.universe.live_cells() {
+                    hashlife.set(x, y, true);
+                }
+
+                for generations in [1, 1, 2, 5, 16, 1, 37] {
+                    sparse.advance(generations);
+                    hashlife.advance(generations);
+                    assert_eq!(live_cells(&sparse), live_cells(&hashlife));
+                    assert_eq!(sparse.alive_count(), hashlife.alive_count());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn mirror_edges_reflect_the_cells_along_them() {
+        // with its three mirror images, a cell in the corner is a block
+        let mut board = board_with(4, 4, &[(0, 0)]);
+        board.edges = Edges::Mirror;
+        for _ in 0..3 {
+            board.step();
+            assert_eq!(live_cells(&board), [(0, 0)]);
+        }
+
+        board.edges = Edges::Dead;
+        board.step();
+        assert_eq!(board.alive_count(), 0);
+    }
+
+    #[test]
+    fn placed_patterns_wrap_only_on_a_torus() {
+        let pattern = vec![vec![true, false], vec![true, true]];
+
+        // the dead cell of the pattern lands on (0, 2) only on a torus
+        let mut board = board_with(4, 4, &[(0, 2)]);
+        board.place(&pattern, (3, 2));
+        assert_eq!(live_cells(&board), [(0, 3), (3, 2), (3, 3)]);
+
+        let mut board = board_with(4, 4, &[(0, 2)]);
+        board.edges = Edges::Dead;
+        board.place(&pattern, (3, 2));
+        assert_eq!(live_cells(&board), [(0, 2), (3, 2), (3, 3)]);
+    }
+
+    #[test]
+    fn every_cell_counts_towards_the_hash() {
+        let empty = Board::new(4, 4).universe.cells_hash();
+        for (x, y) in [(0, 0), (3, 0), (0, 3), (2, 1)] {
+            let board = board_with(4, 4, &[(x, y)]);
+            assert_ne!(board.universe.cells_hash(), empty, "({x}, {y})");
+            let mut sparse = Sparse::default();
+            sparse.set(x, y, true);
+            assert_eq!(sparse.cells_hash(), board.universe.cells_hash());
+        }
+    }
+}
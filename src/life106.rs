@@ -0,0 +1,57 @@
+//! Parser for the Life 1.06 format.
+//!
+//! After a `#Life 1.06` header every line holds the signed `x y` coordinates
+//! of one living cell. The pattern is cropped to the bounding box of those
+//! cells, so where the coordinates start does not matter.
+
+use anyhow::{Context, bail};
+
+pub const HEADER: &str = "#Life 1.06";
+
+pub fn parse(input: &str) -> anyhow::Result<Vec<Vec<bool>>> {
+    let mut lines = input.lines().enumerate();
+    match lines.next() {
+        Some((_, line)) if line.trim_end() == HEADER => {}
+        _ => bail!("line 1: expected {HEADER:?}"),
+    }
+
+    let mut cells = Vec::new();
+    for (index, line) in lines {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let mut coordinates = line.split_whitespace().map(str::parse::<i64>);
+        let (Some(x), Some(y), None) = (coordinates.next(), coordinates.next(), coordinates.next())
+        else {
+            bail!("line {}: expected two coordinates, got {line:?}", index + 1);
+        };
+        let x = x.with_context(|| format!("line {}: invalid x coordinate", index + 1))?;
+        let y = y.with_context(|| format!("line {}: invalid y coordinate", index + 1))?;
+        cells.push((x, y));
+    }
+
+    let Some(&(first_x, first_y)) = cells.first() else {
+        return Ok(Vec::new());
+    };
+    let (mut left, mut top, mut right, mut bottom) = (first_x, first_y, first_x, first_y);
+    for &(x, y) in &cells {
+        left = left.min(x);
+        top = top.min(y);
+        right = right.max(x);
+        bottom = bottom.max(y);
+    }
+
+    let width = right.abs_diff(left) + 1;
+    let height = bottom.abs_diff(top) + 1;
+    if width > u16::MAX as u64 || height > u16::MAX as u64 {
+        bail!("pattern is {width}x{height}, larger than the biggest field");
+    }
+
+    let mut pattern = vec![vec![false; width as usize]; height as usize];
+    for (x, y) in cells {
+        pattern[y.abs_diff(top) as usize][x.abs_diff(left) as usize] = true;
+    }
+    Ok(pattern)
+}
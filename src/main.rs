@@ -1,109 +1,1150 @@
-use std::io::Write;
+mod bench;
+mod export;
+mod keys;
+mod session;
+mod theme;
 
+use std::{
+    any::Any,
+    collections::{HashMap, VecDeque},
+    io::Write,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+use anyhow::Context;
+use clap::Parser;
 use crossterm::{
     cursor,
-    event::{Event, KeyCode},
+    event::{self, Event, KeyCode, KeyEvent, MouseButton, MouseEvent, MouseEventKind},
     execute, queue,
-    style::{Color, Print, SetForegroundColor},
+    style::{Attribute, Color, Print, SetAttribute, SetBackgroundColor, SetForegroundColor},
     terminal,
 };
+use game_of_life::{
+    analysis,
+    hashlife::{self, HashLife},
+    life::Board,
+    life106, patterns, plaintext, rle,
+    rule::Rule,
+    universe,
+};
+use keys::{Action, KeyBindings};
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use session::Session;
+use theme::{Theme, ThemeName};
+use universe::{Dense, Edges, Neighborhood, Player, Sparse, Universe};
+
+// while placing a pattern
+const ROTATE_KEY: char = 'r';
+const ROTATE_BACK_KEY: char = 'R';
+const FLIP_KEY: char = 'f';
+const FLIP_VERTICALLY_KEY: char = 'F';
+// while selecting
+const YANK_KEY: char = 'y';
+const CUT_KEY: char = 'x';
+
+const MIN_STEP_INTERVAL: Duration = Duration::from_millis(20);
+const MAX_STEP_INTERVAL: Duration = Duration::from_secs(2);
+// longest period of the oscillators a run pauses at
+const MAX_PERIOD: usize = 64;
+// log2 of the most generations a step can run
+const MAX_WARP: u32 = 40;
+// the same for the cell engines, which compute every generation in turn
+const MAX_CELLS_WARP: u32 = 4;
+// longest wait for input while running, and while stopped
+const POLL_INTERVAL: Duration = Duration::from_millis(16);
+const PAUSED_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+// cycled through by repeated presses of the randomize key, starting at 30%
+const DENSITIES: [f64; 3] = [0.1, 0.3, 0.5];
+
+const MAX_HISTORY: usize = 50;
+
+// (width, height) below which the field is not drawn
+const MIN_TERMINAL_SIZE: (u16, u16) = (3, 3);
+
+// exit code of a batch run stopped by --max-population
+const OVERPOPULATED_EXIT_CODE: i32 = 3;
+
+// default cap on the generations of each soup of --soup-search
+const SOUP_GENERATIONS: u64 = 2000;
+// share of living cells in the soups' fill
+const SOUP_DENSITY: f64 = 0.5;
+// default cap on the generations an analysis runs
+const ANALYSIS_GENERATIONS: u64 = 1000;
+
+const SESSION_FILE: &str = "game-of-life-session.json";
+
+// behind the edges of a wrapping field, with --seam
+const SEAM_BACKGROUND: Color = Color::Rgb {
+    r: 32,
+    g: 32,
+    b: 64,
+};
+
+// behind every `grid_spacing`th row and column, with --grid
+const GRID_BACKGROUND: Color = Color::Rgb {
+    r: 40,
+    g: 40,
+    b: 40,
+};
 
-const QUIT_KEY: char = 'q';
-const STOP_KEY: char = 's';
-const TOGGLE_VIEW_KEY: char = 'v';
-const TOGGLE_CELL_KEY: char = ' ';
-const UP_KEY_ALT: char = 'k';
-const DOWN_KEY_ALT: char = 'j';
-const LEFT_KEY_ALT: char = 'h';
-const RIGHT_KEY_ALT: char = 'l';
+// share of a cell's heat left after each step of the heatmap
+const HEAT_DECAY: f32 = 0.8;
+// heat of a cell born or dying every step, which the decay evens out at
+const MAX_HEAT: f32 = 1.0 / (1.0 - HEAT_DECAY);
+// heat below which a cell is cool again
+const MIN_HEAT: f32 = 0.05;
 
 const LIVING: char = '■';
 const DEAD: char = '□';
 
-const STATE: [char; 4] = [' ', '▀', '▄', '█'];
-
-const DIRECTIONS: [(i32, i32); 8] = [
-    (-1, -1),
-    (-1, 0),
-    (-1, 1),
-    (0, -1),
-    (0, 1),
-    (1, -1),
-    (1, 0),
-    (1, 1),
+// indexed by a bit per cell: top, bottom
+const HALF_BLOCKS: [char; 4] = [' ', '▀', '▄', '█'];
+// indexed by a bit per cell: top-left, top-right, bottom-left, bottom-right
+const QUADRANTS: [char; 16] = [
+    ' ', '▘', '▝', '▀', '▖', '▌', '▞', '▛', '▗', '▚', '▐', '▜', '▄', '▙', '▟', '█',
 ];
+// bit of the braille pattern for each cell, left to right and top to bottom
+const BRAILLE_DOTS: [u32; 8] = [0x01, 0x08, 0x02, 0x10, 0x04, 0x20, 0x40, 0x80];
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum View {
+    // one cell per character
+    Normal,
+    // 1x2 cells per character
+    HalfBlock,
+    // 2x2 cells per character
+    Quadrant,
+    // 2x4 cells per character, as braille dots
+    Braille,
+}
+
+impl View {
+    fn next(self) -> View {
+        match self {
+            View::Normal => View::HalfBlock,
+            View::HalfBlock => View::Quadrant,
+            View::Quadrant => View::Braille,
+            View::Braille => View::Normal,
+        }
+    }
+
+    /// Returns how many field cells one character covers, as `(width, height)`.
+    fn cell_size(self) -> (usize, usize) {
+        match self {
+            View::Normal => (1, 1),
+            View::HalfBlock => (1, 2),
+            View::Quadrant => (2, 2),
+            View::Braille => (2, 4),
+        }
+    }
+
+    /// Returns the character for a block of cells, `glyphs` being the
+    /// `(living, dead)` characters of the normal view.
+    fn glyph(self, state: usize, (living, dead): (char, char)) -> char {
+        match self {
+            View::Normal => {
+                if state == 0 {
+                    dead
+                } else {
+                    living
+                }
+            }
+            View::HalfBlock => HALF_BLOCKS[state],
+            View::Quadrant => QUADRANTS[state],
+            View::Braille => {
+                let dots = BRAILLE_DOTS
+                    .iter()
+                    .enumerate()
+                    .filter(|&(cell, _)| state & 1 << cell != 0)
+                    .fold(0, |dots, (_, dot)| dots | dot);
+                char::from_u32(0x2800 + dots).expect("braille patterns are characters")
+            }
+        }
+    }
+}
+
+/// A character of the field as drawn on screen.
+#[derive(Clone, Copy, PartialEq)]
+struct ScreenCell {
+    character: char,
+    foreground: Color,
+    background: Color,
+    // drawn with the colors swapped
+    inverted: bool,
+}
+
+/// The last frame drawn, so that the next one only redraws what changed.
+struct Frame {
+    // the terminal size and first field row it was drawn for
+    size: (u16, u16),
+    field_top: u16,
+    header: Option<String>,
+    status: String,
+    // a cell per character of the field rows
+    cells: Vec<Vec<ScreenCell>>,
+}
+
+enum Mode {
+    Edit,
+    // choosing a built-in pattern, by index into the patterns matching `query`
+    PickPattern {
+        query: String,
+        index: usize,
+    },
+    // previewing a pattern at the cursor before stamping it, or before
+    // pasting it when it comes from the clipboard
+    Place {
+        name: &'static str,
+        pattern: Vec<Vec<bool>>,
+        paste: bool,
+    },
+    // extending a rectangle from `anchor` to the cursor
+    Select {
+        anchor: (i64, i64),
+    },
+    // typing a command after ':'
+    Prompt {
+        input: String,
+    },
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum PasteMode {
+    // pasted cells replace the field, dead ones included
+    Overwrite,
+    // only the living pasted cells are added to the field
+    Or,
+}
+
+/// A pattern placed at startup, given as `name@x,y` with the name of a
+/// built-in pattern or a pattern file.
+#[derive(Clone)]
+struct Placement {
+    source: String,
+    position: (i64, i64),
+}
+
+impl FromStr for Placement {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Placement> {
+        let Some((source, position)) = s.rsplit_once('@') else {
+            anyhow::bail!("expected NAME@X,Y, got {s:?}");
+        };
+        let Some((x, y)) = position.split_once(',') else {
+            anyhow::bail!("expected X,Y after '@', got {position:?}");
+        };
+        let coordinate = |value: &str| {
+            value
+                .trim()
+                .parse::<i64>()
+                .with_context(|| format!("invalid coordinate {value:?}"))
+        };
+        Ok(Placement {
+            source: source.to_string(),
+            position: (coordinate(x)?, coordinate(y)?),
+        })
+    }
+}
+
+impl Placement {
+    /// Looks the pattern up in the built-in library, then on disk.
+    fn pattern(&self) -> anyhow::Result<Vec<Vec<bool>>> {
+        if let Some(pattern) = patterns::find(&self.source) {
+            return Ok(pattern.cells());
+        }
+        let path = Path::new(&self.source);
+        if !path.exists() {
+            anyhow::bail!("no built-in pattern or file matches {:?}", self.source);
+        }
+        read_pattern(path)
+    }
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum Fill {
+    // no living cells
+    Empty,
+    // living cells at the density of the first random fill
+    Random,
+    // every other cell alive, alternating along rows and columns
+    Checker,
+    // every cell alive
+    Full,
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum Topology {
+    // the edges wrap around, making the field a torus
+    Torus,
+    // cells beyond the edges are dead
+    Bounded,
+    // cells beyond the edges mirror those along them
+    Mirror,
+    // an unbounded plane that grows with the pattern
+    Infinite,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum Engine {
+    // one cell at a time, on a grid or a sparse plane by the topology
+    Cells,
+    // a memoized quadtree on the infinite plane, fastest on regular patterns
+    Hashlife,
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum OutputFormat {
+    // the whole field as `.`/`O` rows
+    Plaintext,
+    // the living cells as `.`/`O` rows, without trailing dead cells
+    Cells,
+    // the living cells as RLE
+    Rle,
+}
+
+#[derive(Parser)]
+#[command(version, about, args_conflicts_with_subcommands = true)]
+#[command(group(clap::ArgGroup::new("batch").args(["headless", "record", "soup_search", "analyze"]).multiple(true)))]
+struct Args {
+    /// Pattern file to load (.cells, .rle or Life 1.06)
+    pattern: Option<PathBuf>,
+    /// Pattern file to load, as an alternative to the positional argument
+    #[arg(long, visible_alias = "load", conflicts_with = "pattern")]
+    input: Option<PathBuf>,
+    /// Built-in pattern to load, matched loosely by name, e.g. gosperglidergun
+    #[arg(long, value_name = "NAME", conflicts_with_all = ["pattern", "input"])]
+    builtin: Option<String>,
+    /// Cells alive at startup, beneath the pattern if one is loaded
+    #[arg(long, value_enum, default_value_t = Fill::Empty)]
+    fill: Fill,
+    /// Fill the field by chance with this share of living cells, from 0 to 1,
+    /// instead of one of the fills
+    #[arg(long, value_name = "DENSITY", num_args = 0..=1, default_missing_value = "0.3", value_parser = parse_density, conflicts_with = "fill")]
+    random: Option<f64>,
+    /// Place a built-in pattern or pattern file with its top-left corner at
+    /// x,y; repeat to compose several
+    #[arg(long, value_name = "NAME@X,Y")]
+    place: Vec<Placement>,
+    /// Seed of the random fills, so that a run can be reproduced
+    /// [default: a random seed, shown alongside the fill]
+    #[arg(long)]
+    seed: Option<u64>,
+    /// Life-like rule in B/S notation, e.g. B36/S23 for HighLife
+    /// [default: the rule in the header of an RLE pattern, or B3/S23]
+    #[arg(long)]
+    rule: Option<Rule>,
+    /// Cells counted as neighbors; the rule's digits count living neighbors,
+    /// so with the 4 von Neumann neighbors only 0 to 4 matter
+    #[arg(long, value_enum, default_value_t = Neighborhood::Moore)]
+    neighborhood: Neighborhood,
+    /// Play the two-sided Immigration variant, where cells are born to the
+    /// side of most of their neighbors; the space key cycles through sides
+    #[arg(long)]
+    immigration: bool,
+    /// Width of the field in cells [default: fits the terminal, or the pattern when headless or recording]
+    #[arg(long, value_parser = clap::value_parser!(u16).range(1..))]
+    width: Option<u16>,
+    /// Height of the field in cells [default: fits the terminal, or the pattern when headless or recording]
+    #[arg(long, value_parser = clap::value_parser!(u16).range(1..))]
+    height: Option<u16>,
+    /// Shape of the field; on the infinite plane the width and height only
+    /// set the area that is randomized and exported. The edges key cycles
+    /// through the finite ones
+    #[arg(long, value_enum, default_value_t = Topology::Torus)]
+    topology: Topology,
+    /// Same as --topology infinite
+    #[arg(long, conflicts_with = "topology")]
+    infinite: bool,
+    /// How generations are computed; hashlife plays on the infinite plane
+    /// whatever the topology, and lets the warp keys run millions of
+    /// generations of guns and breeders in moments
+    #[arg(long, value_enum, default_value_t = Engine::Cells)]
+    engine: Engine,
+    /// Threads computing each generation of a finite field, 0 for one per
+    /// core; more than one only pays off on large, busy fields
+    #[arg(long, default_value_t = 1)]
+    threads: u16,
+    /// How pasting a yanked selection combines it with the field
+    #[arg(long, value_enum, default_value_t = PasteMode::Overwrite)]
+    paste_mode: PasteMode,
+    /// Character drawn for living cells in the normal view
+    #[arg(long, default_value_t = LIVING)]
+    alive_char: char,
+    /// Character drawn for dead cells in the normal view
+    #[arg(long, default_value_t = DEAD)]
+    dead_char: char,
+    /// Generations run by one press of the fast-forward key
+    #[arg(long, value_name = "GENERATIONS", default_value_t = 100, value_parser = clap::value_parser!(u64).range(1..))]
+    fast_forward: u64,
+    /// Generations kept for the step-back key
+    #[arg(long, value_name = "GENERATIONS", default_value_t = 100, value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..=10_000))]
+    rewind: usize,
+    /// Milliseconds between generations at startup, from 20 to 2000; the
+    /// speed keys halve and double it
+    #[arg(long, value_name = "MS", default_value_t = 200, value_parser = clap::value_parser!(u64).range(20..=2000))]
+    speed: u64,
+    /// Mark the edges of a wrapping field, where cells leaving one side
+    /// come back on the other
+    #[arg(long)]
+    seam: bool,
+    /// Mark every Nth row and column, like graph paper; the grid key
+    /// toggles it [default: every 10th for the key]
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u16).range(1..))]
+    grid: Option<u16>,
+    /// Colors of the field on screen
+    #[arg(long, value_enum, default_value_t = ThemeName::Classic)]
+    theme: ThemeName,
+    /// Pause after this many generations, as does each press of the run-for
+    /// key [default: 100 for the key]
+    #[arg(long, value_name = "GENERATIONS", value_parser = clap::value_parser!(u64).range(1..), conflicts_with = "batch")]
+    run_for: Option<u64>,
+    /// Living cells beyond which the status bar turns red, and headless runs
+    /// and recordings stop with exit code 3
+    #[arg(long, value_name = "N")]
+    max_population: Option<usize>,
+    /// Run without a terminal and print the final field to stdout
+    #[arg(long)]
+    headless: bool,
+    /// Search random soups for ones that stay active or send out gliders,
+    /// printing the seed and population of each; --generations caps each
+    /// soup [default cap: 2000]
+    #[arg(long, conflicts_with_all = ["headless", "record", "pattern", "input", "builtin", "topology", "infinite"])]
+    soup_search: bool,
+    /// Print whether the field is a still life, an oscillator or a
+    /// spaceship, running it on the infinite plane until its shape comes
+    /// back, up to --generations [default cap: 1000]
+    #[arg(long, conflicts_with_all = ["headless", "record", "soup_search"])]
+    analyze: bool,
+    /// Write each analysis, of --analyze or the analyze key, to this file
+    #[arg(long, value_name = "PATH")]
+    report: Option<PathBuf>,
+    /// Number of soups to search, seeded from --seed onward
+    #[arg(long, default_value_t = 100, requires = "soup_search")]
+    trials: u64,
+    /// Size of each soup, as WIDTHxHEIGHT
+    #[arg(long, value_name = "WIDTHxHEIGHT", default_value = "16x16", value_parser = parse_size, requires = "soup_search")]
+    size: (u16, u16),
+    /// Number of generations to run in headless mode or to record
+    #[arg(long, default_value_t = 0, requires = "batch")]
+    generations: u64,
+    /// Stop early once the field becomes a still life or an oscillator of
+    /// period up to 64
+    #[arg(long, requires = "batch")]
+    stop_on_stable: bool,
+    /// Format of the field printed in headless mode
+    #[arg(long, value_enum, default_value_t = OutputFormat::Plaintext, requires = "headless")]
+    output_format: OutputFormat,
+    /// Write the final field to a PNG image when the run ends, on the
+    /// infinite plane every living cell wherever the window is
+    #[arg(long, value_name = "PATH")]
+    export_png: Option<PathBuf>,
+    /// Record every generation to a looping animated GIF, without a terminal
+    #[arg(long, visible_alias = "export-gif", value_name = "PATH")]
+    record: Option<PathBuf>,
+    /// Number of frames to record, the first generation included, as an
+    /// alternative to --generations
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..), requires = "record", conflicts_with = "generations")]
+    frames: Option<u64>,
+    /// Frames per second of the recorded GIF
+    #[arg(long, default_value_t = 10, value_parser = clap::value_parser!(u16).range(1..=100), requires = "record")]
+    fps: u16,
+    /// Size in pixels of one cell in exported images, and in those saved
+    /// with the image key
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..=64))]
+    cell_size: u32,
+    /// Color of living cells in exported images
+    #[arg(long, default_value = "#000000")]
+    alive_color: export::HexColor,
+    /// Color of dead cells in exported images
+    #[arg(long, default_value = "#ffffff")]
+    dead_color: export::HexColor,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(clap::Subcommand)]
+enum Command {
+    /// Run a pattern on the infinite plane without a terminal and write the
+    /// living cells of the last generation as RLE
+    Simulate {
+        /// Pattern file to run (.cells, .rle or Life 1.06)
+        pattern: PathBuf,
+        /// Life-like rule in B/S notation
+        /// [default: the rule in the header of an RLE pattern, or B3/S23]
+        #[arg(long)]
+        rule: Option<Rule>,
+        /// Number of generations to run
+        #[arg(long, value_parser = clap::value_parser!(u64).range(..=hashlife::MAX_GENERATIONS))]
+        generations: u64,
+        /// Cells counted as neighbors
+        #[arg(long, value_enum, default_value_t = Neighborhood::Moore)]
+        neighborhood: Neighborhood,
+        /// How generations are computed
+        #[arg(long, value_enum, default_value_t = Engine::Hashlife)]
+        engine: Engine,
+        /// RLE file to write the last generation to [default: stdout]
+        #[arg(long, short, value_name = "PATH")]
+        output: Option<PathBuf>,
+    },
+    /// Time every engine on random soups, a glider gun and an empty field
+    Bench {
+        /// Number of generations of each workload
+        #[arg(long, default_value_t = 200, value_parser = clap::value_parser!(u64).range(1..=bench::MAX_GENERATIONS))]
+        generations: u64,
+        /// Seed of the random soups
+        #[arg(long, default_value_t = 0)]
+        seed: u64,
+        /// Threads computing each generation of the dense engine, 0 for one
+        /// per core
+        #[arg(long, default_value_t = 1)]
+        threads: u16,
+    },
+}
+
+impl Args {
+    fn image_colors(&self) -> export::Colors {
+        export::Colors {
+            alive: self.alive_color.0,
+            dead: self.dead_color.0,
+        }
+    }
+}
+
+fn player_color(player: Player) -> Color {
+    match player {
+        Player::A => Color::Red,
+        Player::B => Color::Blue,
+    }
+}
+
+/// Cuts `text` down to `width` characters, ending in an ellipsis if it is
+/// too long.
+fn truncate(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        return text.to_string();
+    }
+    let mut truncated: String = text.chars().take(width.saturating_sub(1)).collect();
+    truncated.push('…');
+    truncated
+}
+
+/// Draws `lines` in a box in the middle of the terminal, cut to fit it.
+fn print_help(
+    stdout: &mut std::io::Stdout,
+    lines: &[String],
+    (width, height): (u16, u16),
+) -> anyhow::Result<()> {
+    let footer = "press any key to close";
+    let inner_width = lines
+        .iter()
+        .map(|line| line.chars().count())
+        .chain([footer.chars().count()])
+        .max()
+        .unwrap_or(0)
+        .min(width.saturating_sub(4).into());
+    // the borders around the lines, a blank line and the footer
+    let inner_height = (lines.len() + 2).min(height.saturating_sub(2).into());
+    let left = (width as usize).saturating_sub(inner_width + 4) / 2;
+    let top = (height as usize).saturating_sub(inner_height + 2) / 2;
+
+    let body = lines
+        .iter()
+        .map(String::as_str)
+        .chain(["", footer])
+        .take(inner_height);
+    let rows = std::iter::once(format!("┌{}┐", "─".repeat(inner_width + 2)))
+        .chain(body.map(|line| format!("│ {:inner_width$} │", truncate(line, inner_width))))
+        .chain([format!("└{}┘", "─".repeat(inner_width + 2))]);
+
+    queue!(
+        stdout,
+        SetForegroundColor(Color::Reset),
+        SetBackgroundColor(Color::Reset)
+    )?;
+    for (y, row) in rows.enumerate() {
+        queue!(
+            stdout,
+            cursor::MoveTo(left as u16, (top + y) as u16),
+            Print(row)
+        )?;
+    }
+    Ok(())
+}
+
+fn read_pattern(path: &Path) -> anyhow::Result<Vec<Vec<bool>>> {
+    let input = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    let is_rle = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("rle"));
+    if input.starts_with(life106::HEADER) {
+        life106::parse(&input)
+    } else if is_rle {
+        rle::parse(&input)
+    } else {
+        plaintext::parse(&input)
+    }
+    .with_context(|| format!("failed to parse {}", path.display()))
+}
+
+/// Reads the rule an RLE pattern file was made for, `None` for other formats,
+/// a header without a rule or, with a warning, a rule not in B/S notation.
+fn read_pattern_rule(path: &Path) -> anyhow::Result<Option<Rule>> {
+    if !path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("rle"))
+    {
+        return Ok(None);
+    }
+    let input = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    match rle::rule(&input) {
+        Ok(rule) => Ok(rule),
+        Err(error) => {
+            eprintln!(
+                "warning: ignoring the rule in {}: {error:#}; playing {}",
+                path.display(),
+                Rule::CONWAY
+            );
+            Ok(None)
+        }
+    }
+}
 
 struct Game {
-    // true: living, false: dead
-    field: Vec<Vec<bool>>,
+    board: Board,
+    // size of a finite universe, or of the area of an infinite one that is
+    // randomized and exported
     width: u16,
     height: u16,
+    // true: the size follows the terminal as it is resized
+    fit_terminal: bool,
     stop: bool,
-    cursor: (u16, u16),
-    detail_view: bool,
+    // in field coordinates
+    cursor: (i64, i64),
+    // field coordinates of the top-left cell on screen
+    viewport: (i64, i64),
+    view: View,
+    // true: the edges of a wrapping field are drawn on their own background
+    show_seam: bool,
+    // true: every `grid_spacing`th row and column is drawn on its own
+    // background, like graph paper
+    show_grid: bool,
+    grid_spacing: u16,
+    // false: the field takes the header's row as well
+    show_header: bool,
+    // true: the list of keys is drawn over the field
+    show_help: bool,
+    // false: living cells are all drawn in the color of newborns
+    age_colors: bool,
+    // true: the terminal takes 24-bit colors, so ages are drawn as a gradient
+    true_color: bool,
+    // true: the cells born in the last step are drawn green and those that
+    // died red
+    show_diff: bool,
+    // file each analysis is written to
+    report: Option<PathBuf>,
+    // pixels per cell and colors of saved images
+    cell_size: u32,
+    image_colors: export::Colors,
+    // Some: the births and deaths of each cell since the heatmap was shown,
+    // fading each step, drawn behind the cells
+    heat: Option<HashMap<(i64, i64), f32>>,
+    // (living, dead) characters of the normal view
+    glyphs: (char, char),
+    theme: Theme,
+    mode: Mode,
+    // true: cells are drawn by side rather than age, and toggled through both
+    immigration: bool,
+    // true: the cursor moves across the edges, set along with `board.edges`
+    wrap_cursor: bool,
+    step_interval: Duration,
+    // log2 of the generations each step runs
+    warp: u32,
+    // generations run by one press of the fast-forward key
+    fast_forward: u64,
+    // generation at which the run pauses, cleared once reached
+    target: Option<u64>,
+    // generations run by a press of the run-for key
+    run_for: u64,
+    // living cells beyond which the run is considered a runaway
+    max_population: Option<usize>,
+    generation: u64,
+    // the fields of the last steps and their generations, most recent last,
+    // for stepping backward; cleared by manual edits
+    rewind: VecDeque<(Box<dyn Universe>, u64)>,
+    rewind_length: usize,
+    // hashes of the cells of the last `MAX_PERIOD` generations, the current
+    // one last, for detecting oscillators; cleared by manual edits and
+    // changes to how the cells step
+    hashes: VecDeque<u64>,
+    // generation the field settled into a still life or an oscillator at,
+    // and its period
+    stable: Option<(u64, u64)>,
+    // Some(alive): moving the cursor sets the cells it lands on
+    pen: Option<bool>,
+    // share of living cells in the last random fill
+    density: Option<f64>,
+    // the seed of `rng`, which makes every random fill, so shown to reproduce them
+    seed: u64,
+    rng: StdRng,
+    // field snapshots taken before manual edits
+    undo_stack: Vec<Box<dyn Universe>>,
+    redo_stack: Vec<Box<dyn Universe>>,
+    // the last yanked selection
+    clipboard: Option<Vec<Vec<bool>>>,
+    paste_mode: PasteMode,
+    // shown in place of the help line until the next key press
+    message: Option<String>,
+    // None: the next frame is drawn in full
+    frame: Option<Frame>,
 }
 
 impl Game {
-    fn try_new() -> anyhow::Result<Game> {
-        let (width, height) = terminal::size()?;
-        Ok(Game {
-            field: vec![vec![false; width as usize]; height as usize],
+    fn new(width: u16, height: u16) -> Game {
+        let seed = rand::random();
+        Game {
+            board: Board::new(width, height),
             width,
             height,
+            fit_terminal: false,
             stop: true,
             cursor: (0, 0),
-            detail_view: false,
-        })
+            viewport: (0, 0),
+            view: View::Normal,
+            show_seam: false,
+            show_grid: false,
+            grid_spacing: 10,
+            show_header: true,
+            show_help: false,
+            age_colors: true,
+            true_color: false,
+            show_diff: false,
+            report: None,
+            cell_size: 1,
+            image_colors: export::Colors {
+                alive: image::Rgb([0, 0, 0]),
+                dead: image::Rgb([255, 255, 255]),
+            },
+            heat: None,
+            glyphs: (LIVING, DEAD),
+            theme: ThemeName::Classic.theme(),
+            mode: Mode::Edit,
+            immigration: false,
+            wrap_cursor: true,
+            step_interval: Duration::from_millis(200),
+            warp: 0,
+            fast_forward: 100,
+            max_population: None,
+            target: None,
+            run_for: 100,
+            generation: 0,
+            rewind: VecDeque::new(),
+            // headless runs only need the last two generations, one of them
+            // to step into
+            rewind_length: 2,
+            hashes: VecDeque::new(),
+            stable: None,
+            pen: None,
+            density: None,
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            clipboard: None,
+            paste_mode: PasteMode::Overwrite,
+            message: None,
+            frame: None,
+        }
     }
 
-    fn print_field(&self) -> anyhow::Result<()> {
+    /// Stamps a pattern into the middle of the field.
+    fn place_centered(&mut self, pattern: &[Vec<bool>]) -> anyhow::Result<()> {
+        let pattern_height = pattern.len();
+        let pattern_width = pattern.first().map_or(0, Vec::len);
+        let (width, height) = (self.width as usize, self.height as usize);
+        if width < pattern_width || height < pattern_height {
+            anyhow::bail!(
+                "pattern is {pattern_width}x{pattern_height} but the field is only {width}x{height}"
+            );
+        }
+
+        let left = (width - pattern_width) / 2;
+        let top = (height - pattern_height) / 2;
+        self.board.place(pattern, (left as i64, top as i64));
+        Ok(())
+    }
+
+    /// Draws the field, `(index, count)` telling which of the open boards it
+    /// is.
+    fn print_field(
+        &mut self,
+        keys: &KeyBindings,
+        (index, count): (usize, usize),
+    ) -> anyhow::Result<()> {
         let mut stdout = std::io::stdout();
-        queue!(
-            stdout,
-            cursor::MoveTo(0, 0),
-            Print(
-                "Press 'q' to quit, 's' to stop, 'v' to toggle view, 'space' to toggle cell, arrow keys to move cursor"
-            )
-        )?;
 
         let (width, height) = terminal::size()?;
+        if width < MIN_TERMINAL_SIZE.0 || height < MIN_TERMINAL_SIZE.1 {
+            let notice: String = "Terminal too small".chars().take(width.into()).collect();
+            queue!(
+                stdout,
+                terminal::Clear(terminal::ClearType::All),
+                cursor::MoveTo(0, 0),
+                SetForegroundColor(Color::Reset),
+                Print(notice)
+            )?;
+            stdout.flush()?;
+            self.frame = None;
+            return Ok(());
+        }
+
+        let density = self
+            .density
+            .map(|density| format!(" | {:.0}% random, seed {}", density * 100.0, self.seed))
+            .unwrap_or_default();
+        let pen = match self.pen {
+            Some(true) => " | pen",
+            Some(false) => " | eraser",
+            None => "",
+        };
+        let header = match (&self.mode, &self.message) {
+            (Mode::PickPattern { query, index }, _) => {
+                let matches = patterns::search(query);
+                format!(
+                    "Pattern {}/{} matching {query:?}: {} (type to search, up/down to choose, 'enter' to select, 'esc' to cancel)",
+                    (index + 1).min(matches.len()),
+                    matches.len(),
+                    matches.get(*index).map_or("none", |pattern| pattern.name)
+                )
+            }
+            (Mode::Place { name, .. }, _) => format!(
+                "Placing {name}: arrow keys to move, 'r'/'R' to rotate, 'f'/'F' to flip, 'enter' to place, 'esc' to cancel"
+            ),
+            (Mode::Prompt { input }, _) => {
+                format!(
+                    ":{input} (x,y to go to a cell, rule B/S to change the rule, w NAME to save, run N to run N generations, 'enter' to run, 'esc' to cancel)"
+                )
+            }
+            (Mode::Select { .. }, _) => {
+                let (left, top, right, bottom) = self.selection().unwrap_or_default();
+                format!(
+                    "Selecting {}x{}: arrow keys to extend, 'y' to yank, 'x' to cut, 'esc' to cancel",
+                    right - left + 1,
+                    bottom - top + 1
+                )
+            }
+            (_, Some(message)) => message.clone(),
+            (_, None) => keys.hint(),
+        };
+        let field_top = self.field_top();
+        let previous = self
+            .frame
+            .take()
+            .filter(|frame| frame.size == (width, height) && frame.field_top == field_top);
+        if previous.is_none() {
+            queue!(
+                stdout,
+                SetBackgroundColor(Color::Reset),
+                terminal::Clear(terminal::ClearType::All)
+            )?;
+        }
 
-        if self.detail_view {
-            for y in (1..(height.min(self.height) - 1).max(1)).step_by(2) {
-                for x in 0..width.min(self.width) {
-                    let mut state = 0;
-                    for dy in 0..2 {
-                        if self.field[(y + dy) as usize - 1][x as usize] {
-                            state |= 1 << (dy);
+        let header = self.show_header.then(|| truncate(&header, width.into()));
+        if let Some(header) = &header
+            && previous
+                .as_ref()
+                .is_none_or(|frame| frame.header.as_ref() != Some(header))
+        {
+            queue!(
+                stdout,
+                cursor::MoveTo(0, 0),
+                Print(header),
+                terminal::Clear(terminal::ClearType::UntilNewLine)
+            )?;
+        }
+
+        let field_rows = self.field_rows(height);
+        let (cell_width, cell_height) = self.view.cell_size();
+        let (viewport_x, viewport_y) = self.viewport;
+        let (columns, rows) = match self.board.universe.bounds() {
+            Some((field_width, field_height)) => {
+                let visible = |size: u16, offset: i64, cell_size: usize| {
+                    ((size as i64 - offset).max(0) as usize)
+                        .div_ceil(cell_size)
+                        .min(u16::MAX as usize) as u16
+                };
+                (
+                    width.min(visible(field_width, viewport_x, cell_width)),
+                    field_rows.min(visible(field_height, viewport_y, cell_height)),
+                )
+            }
+            None => (width, field_rows),
+        };
+        let blank = ScreenCell {
+            character: ' ',
+            foreground: Color::Reset,
+            background: self.theme.background,
+            inverted: false,
+        };
+        let mut cells = vec![vec![blank; width.into()]; field_rows.into()];
+        // the generation before the last step, gone after edits
+        let before = self
+            .rewind
+            .back()
+            .filter(|_| self.show_diff)
+            .map(|(universe, _)| &**universe);
+        for y in 0..rows {
+            let top = viewport_y + y as i64 * cell_height as i64;
+            for x in 0..columns {
+                let left = viewport_x + x as i64 * cell_width as i64;
+                let has_cursor = (left..left + cell_width as i64).contains(&self.cursor.0)
+                    && (top..top + cell_height as i64).contains(&self.cursor.1);
+                let selected =
+                    self.selection()
+                        .is_some_and(|(s_left, s_top, s_right, s_bottom)| {
+                            left <= s_right
+                                && s_left < left + cell_width as i64
+                                && top <= s_bottom
+                                && s_top < top + cell_height as i64
+                        });
+                // the cells along the edges of a torus, which border each other
+                let seam = match self.board.universe.bounds() {
+                    Some((field_width, field_height))
+                        if self.show_seam && self.board.edges == Edges::Wrap =>
+                    {
+                        let (right, bottom) = (left + cell_width as i64, top + cell_height as i64);
+                        left <= 0
+                            || (field_width as i64) <= right
+                            || top <= 0
+                            || (field_height as i64) <= bottom
+                    }
+                    _ => false,
+                };
+                let on_line = |start: i64, size: usize| {
+                    (start..start + size as i64)
+                        .any(|position| position.rem_euclid(self.grid_spacing.into()) == 0)
+                };
+                let grid =
+                    self.show_grid && (on_line(left, cell_width) || on_line(top, cell_height));
+                // the hottest of the cells sharing the character
+                let heat = self.heat.as_ref().and_then(|heat| {
+                    (0..cell_height)
+                        .flat_map(|dy| {
+                            (0..cell_width).map(move |dx| (left + dx as i64, top + dy as i64))
+                        })
+                        .filter_map(|position| heat.get(&position).copied())
+                        .reduce(f32::max)
+                });
+                let background = if selected {
+                    Some(Color::DarkGrey)
+                } else if let Some(heat) = heat {
+                    Some(theme::heat(heat / MAX_HEAT, self.true_color))
+                } else if seam {
+                    Some(SEAM_BACKGROUND)
+                } else if grid {
+                    Some(GRID_BACKGROUND)
+                } else {
+                    None
+                };
+                let mut state = 0;
+                let mut pending = false;
+                // a pending cell would land on a living one
+                let mut collision = false;
+                let mut age = None;
+                let (mut live, mut b_count) = (0, 0);
+                let (mut born, mut died) = (false, 0);
+                for dy in 0..cell_height {
+                    for dx in 0..cell_width {
+                        let (cell_x, cell_y) = (left + dx as i64, top + dy as i64);
+                        let is_pending = self.is_pending_at(cell_x, cell_y);
+                        pending |= is_pending;
+                        let is_alive = self.board.get(cell_x, cell_y);
+                        collision |= is_pending && is_alive;
+                        if is_alive {
+                            age = age.max(Some(self.board.universe.age(cell_x, cell_y)));
+                            live += 1;
+                            b_count += usize::from(
+                                self.board.universe.player(cell_x, cell_y) == Player::B,
+                            );
+                        }
+                        if is_pending || is_alive {
+                            state |= 1 << (dy * cell_width + dx);
+                        }
+                        if let Some(before) = before {
+                            let was_alive = before.is_alive(cell_x, cell_y);
+                            born |= is_alive && !was_alive;
+                            if was_alive && !is_alive {
+                                died |= 1 << (dy * cell_width + dx);
+                            }
                         }
                     }
-                    queue!(
-                        stdout,
-                        cursor::MoveTo(x, (y + 1) / 2),
-                        SetForegroundColor(Color::Reset),
-                        Print(STATE[state])
-                    )?;
                 }
+                let foreground = if collision {
+                    Color::Red
+                } else if pending {
+                    Color::DarkGrey
+                } else if self.view == View::Normal && has_cursor {
+                    self.theme.cursor
+                } else if born {
+                    Color::Green
+                } else if died != 0 {
+                    // the dead cells show where the character has no living
+                    // ones
+                    if live == 0 {
+                        state |= died;
+                    }
+                    Color::Red
+                } else if self.immigration && 0 < live {
+                    player_color(universe::majority(live, b_count))
+                } else {
+                    age.map_or(self.theme.dead, |age| {
+                        let age = if self.age_colors { age } else { 1 };
+                        self.theme.alive(age, self.true_color)
+                    })
+                };
+                cells[y as usize][x as usize] = ScreenCell {
+                    character: self.view.glyph(state, self.glyphs),
+                    foreground,
+                    background: background.unwrap_or(self.theme.background),
+                    // a denser view has no room for a cursor color, so the
+                    // character holding the cursor is drawn inverted instead
+                    inverted: self.view != View::Normal && has_cursor,
+                };
             }
-        } else {
-            for y in 1..height.min(self.height) {
-                for x in 0..width.min(self.width) {
+        }
+
+        // only the characters that changed since the last frame are drawn
+        let mut style = None;
+        for (y, row) in cells.iter().enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                if previous
+                    .as_ref()
+                    .is_some_and(|frame| frame.cells[y][x] == *cell)
+                {
+                    continue;
+                }
+                let cell_style = (cell.foreground, cell.background, cell.inverted);
+                if style != Some(cell_style) {
                     queue!(
                         stdout,
-                        cursor::MoveTo(x, y),
-                        SetForegroundColor(if self.cursor == (x, y - 1) {
-                            Color::Cyan
+                        SetForegroundColor(cell.foreground),
+                        SetBackgroundColor(cell.background),
+                        SetAttribute(if cell.inverted {
+                            Attribute::Reverse
                         } else {
-                            Color::Reset
-                        }),
-                        Print(if self.field[y as usize - 1][x as usize] {
-                            LIVING
-                        } else {
-                            DEAD
+                            Attribute::NoReverse
                         })
                     )?;
+                    style = Some(cell_style);
                 }
+                queue!(
+                    stdout,
+                    cursor::MoveTo(x as u16, field_top + y as u16),
+                    Print(cell.character)
+                )?;
+            }
+        }
+        queue!(
+            stdout,
+            SetAttribute(Attribute::NoReverse),
+            SetBackgroundColor(Color::Reset)
+        )?;
+
+        let board = if 1 < count {
+            format!("board {}/{count} | ", index + 1)
+        } else {
+            String::new()
+        };
+        let target = self
+            .target
+            .map(|target| format!(" of {target}"))
+            .unwrap_or_default();
+        let population = match self.max_population {
+            Some(max_population) if self.overpopulated() => {
+                format!("{} > {max_population}", self.population())
             }
+            _ => self.population().to_string(),
+        };
+        // in the denser views, which of the cells sharing the cursor's
+        // character the cursor is on, drawn as that cell alone
+        let sub_cell = if self.view == View::Normal {
+            String::new()
+        } else {
+            let (cell_width, cell_height) = self.view.cell_size();
+            let dx = (self.cursor.0 - self.viewport.0).rem_euclid(cell_width as i64);
+            let dy = (self.cursor.1 - self.viewport.1).rem_euclid(cell_height as i64);
+            let index = dy as usize * cell_width + dx as usize;
+            format!(" {}", self.view.glyph(1 << index, self.glyphs))
+        };
+        let warp = match self.warp() {
+            0 => String::new(),
+            warp => format!(" x{}", 1u64 << warp),
+        };
+        let status = format!(
+            "{board}gen {}{target} | pop {population} | {} {}ms{warp} | {} | {} | ({}, {}){sub_cell} | neighbors {}{density}{pen}",
+            self.generation,
+            match (self.stop, self.stable) {
+                (true, Some((from, _))) if self.population() == 0 =>
+                    format!("died out at generation {from}"),
+                (true, Some((from, period))) => {
+                    format!("stabilized at generation {from} with period {period}")
+                }
+                (true, None) => "paused".to_string(),
+                (false, _) => "running".to_string(),
+            },
+            self.step_interval.as_millis(),
+            self.board.rule,
+            match (self.board.universe.bounds(), self.board.edges) {
+                (None, _) => "infinite",
+                (Some(_), Edges::Wrap) => "torus",
+                (Some(_), Edges::Dead) => "bounded",
+                (Some(_), Edges::Mirror) => "mirror",
+            },
+            self.cursor.0,
+            self.cursor.1,
+            self.board.universe.live_neighbors(
+                self.cursor.0,
+                self.cursor.1,
+                self.board.edges,
+                &self.board.neighborhood
+            ),
+        );
+        // padded to a full-width bar, and cut short so the last row never wraps
+        let status = format!(
+            "{:width$}",
+            truncate(&status, width.into()),
+            width = width.into()
+        );
+        if previous.as_ref().is_none_or(|frame| frame.status != status) {
+            queue!(
+                stdout,
+                cursor::MoveTo(0, height - 1),
+                SetForegroundColor(if self.overpopulated() {
+                    Color::Red
+                } else {
+                    Color::Reset
+                }),
+                SetAttribute(Attribute::Reverse),
+                Print(&status),
+                SetAttribute(Attribute::NoReverse)
+            )?;
+        }
+        self.frame = Some(Frame {
+            size: (width, height),
+            field_top,
+            header,
+            status,
+            cells,
+        });
+
+        if self.show_help {
+            print_help(&mut stdout, &keys.help(), (width, height))?;
         }
 
         stdout.flush()?;
@@ -111,115 +1152,1827 @@ impl Game {
         Ok(())
     }
 
-    fn update(&mut self) -> anyhow::Result<()> {
-        if self.stop {
-            self.print_field()?;
-            return Ok(());
+    fn draw(&mut self, keys: &KeyBindings, boards: (usize, usize)) -> anyhow::Result<()> {
+        self.follow_cursor(terminal::size()?);
+        self.print_field(keys, boards)
+    }
+
+    /// Pans the viewport so that the cursor stays on screen.
+    fn follow_cursor(&mut self, terminal_size: (u16, u16)) {
+        let (visible_width, visible_height) = self.visible_size(terminal_size);
+
+        let scroll = |offset: i64, cursor: i64, visible: u16| {
+            let visible = visible as i64;
+            if cursor < offset {
+                cursor
+            } else if offset + visible <= cursor {
+                cursor + 1 - visible
+            } else {
+                offset
+            }
+        };
+        self.viewport = (
+            scroll(self.viewport.0, self.cursor.0, visible_width),
+            scroll(self.viewport.1, self.cursor.1, visible_height),
+        );
+    }
+
+    /// Moves the viewport and the cursor with it by a screen in each
+    /// direction, stopping at the edges of a finite field.
+    fn pan(&mut self, (dx, dy): (i64, i64)) {
+        let Ok(terminal_size) = terminal::size() else {
+            return;
+        };
+        let (visible_width, visible_height) = self.visible_size(terminal_size);
+        let (dx, dy) = (dx * visible_width as i64, dy * visible_height as i64);
+        let (mut viewport, mut cursor) = (
+            (self.viewport.0 + dx, self.viewport.1 + dy),
+            (self.cursor.0 + dx, self.cursor.1 + dy),
+        );
+        if let Some((width, height)) = self.board.universe.bounds() {
+            let clamp = |offset: i64, visible: u16, size: u16| {
+                offset.min(size as i64 - visible as i64).max(0)
+            };
+            viewport = (
+                clamp(viewport.0, visible_width, width),
+                clamp(viewport.1, visible_height, height),
+            );
+            cursor = (
+                cursor.0.clamp(0, width as i64 - 1),
+                cursor.1.clamp(0, height as i64 - 1),
+            );
         }
+        self.viewport = viewport;
+        self.cursor = cursor;
+    }
+
+    /// Pans the viewport so that the cursor is in the middle of the screen.
+    fn center_viewport(&mut self, terminal_size: (u16, u16)) {
+        let (visible_width, visible_height) = self.visible_size(terminal_size);
+        let center = |cursor: i64, visible: u16, size: Option<u16>| {
+            let offset = cursor - visible as i64 / 2;
+            match size {
+                Some(size) => offset.min(size as i64 - visible as i64).max(0),
+                None => offset,
+            }
+        };
+        let bounds = self.board.universe.bounds();
+        self.viewport = (
+            center(self.cursor.0, visible_width, bounds.map(|(width, _)| width)),
+            center(
+                self.cursor.1,
+                visible_height,
+                bounds.map(|(_, height)| height),
+            ),
+        );
+    }
 
-        let mut new_field = vec![vec![false; self.width.into()]; self.height.into()];
+    /// Picks the least dense view that shows every living cell and centers
+    /// the cursor and viewport on them.
+    fn fit_to_content(&mut self, terminal_size: (u16, u16)) {
+        let Some((left, top, right, bottom)) = self.bounding_box() else {
+            return;
+        };
+        let (width, height) = (right - left + 1, bottom - top + 1);
+        let (columns, rows) = (
+            terminal_size.0 as i64,
+            self.field_rows(terminal_size.1) as i64,
+        );
+        self.view = [View::Normal, View::HalfBlock, View::Quadrant, View::Braille]
+            .into_iter()
+            .find(|view| {
+                let (cell_width, cell_height) = view.cell_size();
+                width <= columns * cell_width as i64 && height <= rows * cell_height as i64
+            })
+            .unwrap_or(View::Braille);
+        self.cursor = (left + width / 2, top + height / 2);
+        self.center_viewport(terminal_size);
+    }
 
-        for (y, row) in new_field.iter_mut().enumerate() {
-            for (x, cell) in row.iter_mut().enumerate() {
-                let live_neighbors = DIRECTIONS
-                    .iter()
-                    .filter(|&&(dx, dy)| self.is_alive_at(x as i32 + dx, y as i32 + dy))
-                    .count();
+    /// Returns the terminal row the field is drawn from, below the header
+    /// unless it is hidden.
+    fn field_top(&self) -> u16 {
+        u16::from(self.show_header)
+    }
 
-                let current_cell_alive = self.is_alive_at(x as i32, y as i32);
+    /// Returns how many of the terminal's `height` rows the field is drawn
+    /// on, between the header and the status bar.
+    fn field_rows(&self, height: u16) -> u16 {
+        height.saturating_sub(self.field_top() + 1)
+    }
 
-                if current_cell_alive {
-                    if live_neighbors == 2 || live_neighbors == 3 {
-                        *cell = true;
-                    }
-                } else if live_neighbors == 3 {
-                    *cell = true;
+    /// Returns how many cells of the field fit on screen.
+    fn visible_size(&self, (width, height): (u16, u16)) -> (u16, u16) {
+        let (cell_width, cell_height) = self.view.cell_size();
+        let visible_width = (width as usize * cell_width).clamp(1, u16::MAX as usize) as u16;
+        let visible_height =
+            (self.field_rows(height) as usize * cell_height).clamp(1, u16::MAX as usize) as u16;
+        (visible_width, visible_height)
+    }
+
+    /// Runs `fast_forward` generations without drawing them, stopping early
+    /// once the field settles.
+    fn fast_forward(&mut self) {
+        let target = self.target;
+        // one generation at a time, whatever the warp
+        for _ in 0..self.fast_forward {
+            self.advance(1);
+            if self.stable.is_some() || target.is_some_and(|target| target <= self.generation) {
+                break;
+            }
+        }
+    }
+
+    /// Returns the warp level steps run at, lower than the one set on an
+    /// engine other than HashLife.
+    fn warp(&self) -> u32 {
+        let universe: &dyn Any = &*self.board.universe;
+        if universe.is::<HashLife>() {
+            self.warp
+        } else {
+            self.warp.min(MAX_CELLS_WARP)
+        }
+    }
+
+    /// Runs 2^`warp` generations, or as many as are left to the target.
+    fn step(&mut self) {
+        let generations = match self.target {
+            Some(target) => (1 << self.warp()).min(target.saturating_sub(self.generation).max(1)),
+            None => 1 << self.warp(),
+        };
+        self.advance(generations);
+    }
+
+    /// Runs `generations` generations, the previous one kept to step back to.
+    fn advance(&mut self, generations: u64) {
+        // the oldest generation recorded makes room for the next one, even
+        // when it is the only one
+        let spare = if generations == 1 && self.rewind.len() == self.rewind_length {
+            self.rewind.pop_front()
+        } else {
+            None
+        };
+        let previous = match spare {
+            Some((spare, _)) => self.board.step_into(spare),
+            None => self.board.advance(generations),
+        };
+
+        // a period only shows between single generations
+        let period = if generations == 1 {
+            if self.hashes.is_empty() {
+                self.hashes.push_back(previous.cells_hash());
+            }
+            let hash = self.board.universe.cells_hash();
+            let period = self
+                .hashes
+                .iter()
+                .rev()
+                .position(|&before| before == hash)
+                .map(|index| index as u64 + 1);
+            if self.hashes.len() == MAX_PERIOD {
+                self.hashes.pop_front();
+            }
+            self.hashes.push_back(hash);
+            period
+        } else {
+            self.hashes.clear();
+            None
+        };
+        if self.rewind.len() == self.rewind_length {
+            self.rewind.pop_front();
+        }
+        if let Some(heat) = &mut self.heat {
+            heat.retain(|_, heat| {
+                *heat *= HEAT_DECAY;
+                MIN_HEAT <= *heat
+            });
+            let (now, before) = (&*self.board.universe, &*previous);
+            let born = now
+                .live_cells()
+                .into_iter()
+                .filter(|&(x, y)| !before.is_alive(x, y));
+            let died = before
+                .live_cells()
+                .into_iter()
+                .filter(|&(x, y)| !now.is_alive(x, y));
+            for position in born.chain(died) {
+                *heat.entry(position).or_default() += 1.0;
+            }
+        }
+        self.rewind.push_back((previous, self.generation));
+        self.generation += generations;
+
+        if let Some(period) = period
+            && self.stable.is_none()
+        {
+            self.stop = true;
+            self.message = Some(if self.population() == 0 {
+                format!("Died out at gen {}", self.generation - period)
+            } else {
+                format!(
+                    "Stabilized at generation {} with period {period}",
+                    self.generation - period
+                )
+            });
+        }
+        self.stable = period.map(|period| match self.stable {
+            Some((from, _)) => (from, period),
+            None => (self.generation - period, period),
+        });
+
+        if let Some(target) = self.target
+            && target <= self.generation
+        {
+            self.target = None;
+            self.stop = true;
+            self.message = Some(format!("Paused at target {target}"));
+        }
+    }
+
+    /// Analyzes the living cells for up to `generations` generations and
+    /// writes the report to the report file, if there is one.
+    fn analyze(&self, generations: u64) -> anyhow::Result<String> {
+        let behavior = analysis::analyze(
+            &self.board.universe.live_cells(),
+            &self.board.rule,
+            &self.board.neighborhood,
+            generations,
+        )?;
+        if let Some(path) = &self.report {
+            let report = format!(
+                "rule: {}\npopulation: {}\nbehavior: {behavior}\n",
+                self.board.rule,
+                self.population()
+            );
+            std::fs::write(path, report)
+                .with_context(|| format!("failed to write {}", path.display()))?;
+        }
+        Ok(behavior.to_string())
+    }
+
+    /// Runs the next `generations` generations and pauses there.
+    fn run_for(&mut self, generations: u64) -> anyhow::Result<()> {
+        let target = self.generation.checked_add(generations).with_context(|| {
+            format!(
+                "cannot run {generations} more generations from generation {}",
+                self.generation
+            )
+        })?;
+        self.target = Some(target);
+        self.stop = false;
+        Ok(())
+    }
+
+    /// Goes back to the previous generation, if it is still recorded.
+    fn step_back(&mut self) {
+        if let Some((universe, generation)) = self.rewind.pop_back() {
+            self.board.universe = universe;
+            self.generation = generation;
+            self.hashes.pop_back();
+            self.stable = None;
+            self.stop = true;
+        }
+    }
+
+    fn population(&self) -> usize {
+        self.board.alive_count()
+    }
+
+    fn overpopulated(&self) -> bool {
+        self.max_population
+            .is_some_and(|max_population| max_population < self.population())
+    }
+
+    /// Snapshots the field so the edit about to be made can be undone.
+    fn record_edit(&mut self) {
+        if self.undo_stack.len() == MAX_HISTORY {
+            self.undo_stack.remove(0);
+        }
+        self.undo_stack.push(self.board.universe.clone_box());
+        self.redo_stack.clear();
+        self.rewind.clear();
+        self.hashes.clear();
+    }
+
+    fn undo(&mut self) {
+        let Some(universe) = self.undo_stack.pop() else {
+            self.message = Some("Nothing to undo".to_string());
+            return;
+        };
+        self.redo_stack
+            .push(std::mem::replace(&mut self.board.universe, universe));
+        self.rewind.clear();
+        self.hashes.clear();
+    }
+
+    fn redo(&mut self) {
+        let Some(universe) = self.redo_stack.pop() else {
+            self.message = Some("Nothing to redo".to_string());
+            return;
+        };
+        self.undo_stack
+            .push(std::mem::replace(&mut self.board.universe, universe));
+        self.rewind.clear();
+        self.hashes.clear();
+    }
+
+    fn clear(&mut self) {
+        self.record_edit();
+        self.board.universe.clear();
+        self.generation = 0;
+    }
+
+    fn randomize(&mut self) {
+        self.record_edit();
+        let density = match self.density {
+            Some(last) => DENSITIES
+                .into_iter()
+                .find(|&density| last < density)
+                .unwrap_or(DENSITIES[0]),
+            None => DENSITIES[1],
+        };
+        self.fill_random(density);
+    }
+
+    /// Fills the field by chance, `density` being the share of living cells.
+    fn fill_random(&mut self, density: f64) {
+        self.density = Some(density);
+        self.board.universe.clear();
+        for y in 0..self.height as i64 {
+            for x in 0..self.width as i64 {
+                if self.rng.random_bool(density) {
+                    let player = if self.immigration && self.rng.random_bool(0.5) {
+                        Player::B
+                    } else {
+                        Player::A
+                    };
+                    self.board.universe.set_player(x, y, player);
+                }
+            }
+        }
+        self.generation = 0;
+    }
+
+    /// Replaces the field with one of the starting fills.
+    fn apply_fill(&mut self, fill: Fill) {
+        let alive: fn(i64, i64) -> bool = match fill {
+            Fill::Empty => return self.board.universe.clear(),
+            Fill::Random => return self.fill_random(DENSITIES[1]),
+            Fill::Checker => |x, y| (x + y) % 2 == 0,
+            Fill::Full => |_, _| true,
+        };
+        self.board.universe.clear();
+        for y in 0..self.height as i64 {
+            for x in 0..self.width as i64 {
+                if alive(x, y) {
+                    self.board.set(x, y, true);
                 }
             }
         }
-        self.field = new_field;
+        self.generation = 0;
+    }
+
+    /// Copies a pattern onto the field with its top-left corner at `(x, y)`,
+    /// wrapping around the edges on a torus and clipping them otherwise.
+    fn stamp(&mut self, pattern: &[Vec<bool>], position: (i64, i64)) {
+        self.record_edit();
+        self.draw_pattern(pattern, position);
+    }
 
-        self.print_field()?;
+    /// Copies a pattern like [`Game::stamp`], without recording an edit.
+    fn draw_pattern(&mut self, pattern: &[Vec<bool>], position: (i64, i64)) {
+        self.board.place(pattern, position);
+    }
 
+    /// Resizes the field along with every recorded snapshot of it.
+    fn resize(&mut self, width: u16, height: u16) {
+        self.width = width;
+        self.height = height;
+        for universe in std::iter::once(&mut self.board.universe)
+            .chain(&mut self.undo_stack)
+            .chain(&mut self.redo_stack)
+            .chain(self.rewind.iter_mut().map(|(universe, _)| universe))
         {
-            let (width, height) = terminal::size()?;
-            self.width = width;
-            self.height = height;
+            universe.resize(width, height);
+        }
+        self.hashes.clear();
+        if self.board.universe.bounds().is_some() {
             self.cursor = (
-                self.cursor.0.min(self.width - 1),
-                self.cursor.1.min(self.height - 1),
+                self.cursor.0.min(width as i64 - 1),
+                self.cursor.1.min(height as i64 - 1),
             );
         }
+    }
 
-        Ok(())
+    /// Puts down the pen drawing `alive` cells, starting at the cursor, or
+    /// lifts it if it is already down.
+    fn toggle_pen(&mut self, alive: bool) {
+        if self.pen == Some(alive) {
+            self.pen = None;
+            return;
+        }
+        // a whole stroke is undone at once
+        self.record_edit();
+        self.pen = Some(alive);
+        self.board.set(self.cursor.0, self.cursor.1, alive);
     }
 
+    /// Toggles the field cell under the cursor. The cursor is always in field
+    /// coordinates, so in the denser views it is a single one of the cells
+    /// sharing the inverted character.
     fn toggle_cell(&mut self) {
         let (x, y) = self.cursor;
-        self.field[y as usize][x as usize] = !self.field[y as usize][x as usize];
+        // nothing to toggle, nor an edit to record, off the field
+        if !self.in_field(x, y) {
+            return;
+        }
+        self.record_edit();
+        // in the Immigration variant a dead cell goes to side A, then B
+        match (self.board.get(x, y), self.board.universe.player(x, y)) {
+            (false, _) => self.board.set(x, y, true),
+            (true, Player::A) if self.immigration => {
+                self.board.universe.set_player(x, y, Player::B)
+            }
+            (true, _) => self.board.set(x, y, false),
+        }
+    }
+
+    fn to_rle(&self) -> String {
+        rle::encode_cells(&self.board.universe.live_cells(), &self.board.rule)
+    }
+
+    /// Returns `(left, top, right, bottom)` of the living cells, inclusive.
+    fn bounding_box(&self) -> Option<(i64, i64, i64, i64)> {
+        let mut bounds: Option<(i64, i64, i64, i64)> = None;
+        for (x, y) in self.board.universe.live_cells() {
+            bounds = Some(match bounds {
+                Some((left, top, right, bottom)) => {
+                    (left.min(x), top.min(y), right.max(x), bottom.max(y))
+                }
+                None => (x, y, x, y),
+            });
+        }
+        bounds
+    }
+
+    /// Returns the cells from `(left, top)` to `(right, bottom)`, inclusive.
+    fn area(&self, (left, top, right, bottom): (i64, i64, i64, i64)) -> Vec<Vec<bool>> {
+        (top..=bottom)
+            .map(|y| (left..=right).map(|x| self.board.get(x, y)).collect())
+            .collect()
+    }
+
+    /// Returns the `width` x `height` area at the origin, which is the whole
+    /// of a finite universe.
+    fn window(&self) -> Vec<Vec<bool>> {
+        self.area((0, 0, self.width as i64 - 1, self.height as i64 - 1))
     }
 
-    fn is_alive_at(&self, x: i32, y: i32) -> bool {
-        let nx = (x + self.width as i32) as u16 % self.width;
-        let ny = (y + self.height as i32) as u16 % self.height;
-        *self
-            .field
-            .get(ny as usize)
-            .and_then(|row| row.get(nx as usize))
-            .unwrap_or(&false)
+    /// Returns the bounding box of the living cells as plaintext, named in
+    /// a `!Name:` comment if `name` is given.
+    fn to_plaintext(&self, name: Option<&str>) -> String {
+        let pattern = match self.bounding_box() {
+            Some(bounds) => self.area(bounds),
+            None => Vec::new(),
+        };
+        plaintext::encode_trimmed(&pattern, name)
     }
 
-    fn handle_input(&mut self, event: Event) -> bool {
+    /// Writes the living cells to a new timestamped file, as RLE or, for
+    /// both plaintext formats, as a `.cells` file.
+    fn save_pattern(&self, format: OutputFormat) -> anyhow::Result<String> {
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs();
+        let name = format!("game-of-life-{timestamp}");
+        let (file_name, contents) = match format {
+            OutputFormat::Rle => (format!("{name}.rle"), self.to_rle()),
+            OutputFormat::Plaintext | OutputFormat::Cells => {
+                (format!("{name}.cells"), self.to_plaintext(Some(&name)))
+            }
+        };
+        std::fs::write(&file_name, contents)
+            .with_context(|| format!("failed to write {file_name}"))?;
+        Ok(file_name)
+    }
+
+    /// Returns the whole finite field or, on the infinite plane, the
+    /// bounding box of the living cells, wherever the window is; an error
+    /// instead of laying out more cells than an image holds.
+    fn snapshot(&self) -> anyhow::Result<Vec<Vec<bool>>> {
+        let bounds = match (self.board.universe.bounds(), self.bounding_box()) {
+            (None, Some(bounds)) => bounds,
+            _ => (0, 0, self.width as i64 - 1, self.height as i64 - 1),
+        };
+        let (left, top, right, bottom) = bounds;
+        export::image_size(
+            right.abs_diff(left) + 1,
+            bottom.abs_diff(top) + 1,
+            self.cell_size,
+        )?;
+        Ok(self.area(bounds))
+    }
+
+    /// Writes the snapshot to a new timestamped PNG image.
+    fn save_png(&self) -> anyhow::Result<String> {
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs();
+        let file_name = format!("game-of-life-{timestamp}.png");
+        export::save_png(
+            &self.snapshot()?,
+            self.cell_size,
+            self.image_colors,
+            Path::new(&file_name),
+        )?;
+        Ok(file_name)
+    }
+
+    fn save_session(&self, path: &Path) -> anyhow::Result<()> {
+        // an infinite universe is saved as the area covering the window and
+        // every living cell, moved so that it starts at the origin
+        let (left, top, right, bottom) = match (self.board.universe.bounds(), self.bounding_box()) {
+            (None, Some((left, top, right, bottom))) => (
+                left.min(0),
+                top.min(0),
+                right.max(self.width as i64 - 1),
+                bottom.max(self.height as i64 - 1),
+            ),
+            _ => (0, 0, self.width as i64 - 1, self.height as i64 - 1),
+        };
+        Session {
+            field: plaintext::encode(&self.area((left, top, right, bottom)))
+                .lines()
+                .map(str::to_string)
+                .collect(),
+            infinite: self.board.universe.bounds().is_none(),
+            generation: self.generation,
+            rule: self.board.rule.to_string(),
+            wrap: self.board.edges == Edges::Wrap,
+            mirror: self.board.edges == Edges::Mirror,
+            cursor: (self.cursor.0 - left, self.cursor.1 - top),
+            step_interval_ms: self.step_interval.as_millis() as u64,
+        }
+        .save(path)
+    }
+
+    /// Replaces the game state with a saved session, leaving it untouched if
+    /// the session is invalid.
+    fn load_session(&mut self, path: &Path) -> anyhow::Result<()> {
+        let session = Session::load(path)?;
+
+        let field = plaintext::parse(&session.field.join("\n")).context("invalid field")?;
+        let width = field.first().map_or(0, Vec::len);
+        let height = field.len();
+        if width == 0 || height == 0 || width.max(height) > u16::MAX as usize {
+            anyhow::bail!("invalid field size {width}x{height}");
+        }
+        let rule = session.rule.parse()?;
+        let (width, height) = (width as u16, height as u16);
+        let mut universe: Box<dyn Universe> = if session.infinite {
+            self.empty_plane()
+        } else {
+            Box::new(Dense::new(width, height))
+        };
+        for (y, row) in field.iter().enumerate() {
+            for (x, &alive) in row.iter().enumerate() {
+                universe.set(x as i64, y as i64, alive);
+            }
+        }
+        let cursor = if session.infinite {
+            session.cursor
+        } else {
+            (
+                session.cursor.0.clamp(0, width as i64 - 1),
+                session.cursor.1.clamp(0, height as i64 - 1),
+            )
+        };
+
+        *self = Game {
+            board: Board {
+                universe,
+                rule,
+                neighborhood: std::mem::take(&mut self.board.neighborhood),
+                edges: match (session.wrap, session.mirror) {
+                    (true, _) => Edges::Wrap,
+                    (false, true) => Edges::Mirror,
+                    (false, false) => Edges::Dead,
+                },
+            },
+            wrap_cursor: session.wrap,
+            cursor,
+            generation: session.generation,
+            step_interval: Duration::from_millis(session.step_interval_ms)
+                .clamp(MIN_STEP_INTERVAL, MAX_STEP_INTERVAL),
+            stop: self.stop,
+            clipboard: self.clipboard.take(),
+            ..self.fresh(width, height)
+        };
+        Ok(())
+    }
+
+    /// Returns an empty infinite plane, on the same engine as this game's if
+    /// it plays on one.
+    fn empty_plane(&self) -> Box<dyn Universe> {
+        if self.board.universe.bounds().is_some() {
+            return Box::new(Sparse::default());
+        }
+        let mut universe = self.board.universe.clone_box();
+        universe.clear();
+        universe
+    }
+
+    /// Returns an empty, stopped game of the given size, with the same kind
+    /// of universe as this one and its rule and settings.
+    fn fresh(&self, width: u16, height: u16) -> Game {
+        let game = Game::new(width, height);
+        let universe = match self.board.universe.bounds() {
+            Some(_) => game.board.universe,
+            None => self.empty_plane(),
+        };
+        Game {
+            board: Board {
+                universe,
+                rule: self.board.rule,
+                neighborhood: self.board.neighborhood.clone(),
+                edges: self.board.edges,
+            },
+            wrap_cursor: self.wrap_cursor,
+            fit_terminal: self.fit_terminal,
+            step_interval: self.step_interval,
+            warp: self.warp,
+            fast_forward: self.fast_forward,
+            max_population: self.max_population,
+            run_for: self.run_for,
+            view: self.view,
+            show_seam: self.show_seam,
+            show_grid: self.show_grid,
+            grid_spacing: self.grid_spacing,
+            show_header: self.show_header,
+            age_colors: self.age_colors,
+            true_color: self.true_color,
+            show_diff: self.show_diff,
+            report: self.report.clone(),
+            cell_size: self.cell_size,
+            image_colors: self.image_colors,
+            heat: self.heat.as_ref().map(|_| HashMap::new()),
+            glyphs: self.glyphs,
+            theme: self.theme,
+            immigration: self.immigration,
+            rewind_length: self.rewind_length,
+            paste_mode: self.paste_mode,
+            seed: self.seed,
+            rng: self.rng.clone(),
+            ..game
+        }
+    }
+
+    fn in_field(&self, x: i64, y: i64) -> bool {
+        self.board.universe.bounds().is_none_or(|(width, height)| {
+            (0..width as i64).contains(&x) && (0..height as i64).contains(&y)
+        })
+    }
+
+    fn handle_input(&mut self, event: Event, keys: &KeyBindings) -> bool {
+        if let Event::Resize(width, height) = event {
+            if self.fit_terminal && MIN_TERMINAL_SIZE.0 <= width && MIN_TERMINAL_SIZE.1 <= height {
+                // a finite field shrinks no further than its living cells,
+                // the viewport scrolling over what no longer fits on screen
+                let (width, height) = match self.bounding_box() {
+                    Some((_, _, right, bottom)) if self.board.universe.bounds().is_some() => {
+                        (width.max(right as u16 + 1), height.max(bottom as u16 + 1))
+                    }
+                    _ => (width, height),
+                };
+                self.resize(width, height);
+            }
+            return true;
+        }
+
+        // any key closes the list of keys, and does nothing else
+        if self.show_help {
+            if let Event::Key(_) = event {
+                self.show_help = false;
+                // uncovers the field beneath
+                self.frame = None;
+            }
+            return true;
+        }
+
+        match (&self.mode, &event) {
+            (Mode::Edit, _) => {}
+            (Mode::PickPattern { .. }, Event::Key(key_event)) => {
+                self.handle_pattern_picker(key_event.code);
+                return true;
+            }
+            (Mode::Place { .. }, Event::Key(key_event)) => {
+                self.handle_placement(key_event, keys);
+                return true;
+            }
+            (Mode::Select { .. }, Event::Key(key_event)) => {
+                self.handle_selection(key_event, keys);
+                return true;
+            }
+            (Mode::Prompt { .. }, Event::Key(key_event)) => {
+                self.handle_prompt(key_event.code);
+                return true;
+            }
+            _ => return true,
+        }
+
         if let Event::Key(key_event) = event {
-            match key_event.code {
-                KeyCode::Char(QUIT_KEY) => return false, // Indicate quit
-                KeyCode::Char(STOP_KEY) => self.stop = !self.stop,
-                KeyCode::Char(TOGGLE_VIEW_KEY) => self.detail_view = !self.detail_view,
-                KeyCode::Char(TOGGLE_CELL_KEY) => self.toggle_cell(),
-                KeyCode::Up | KeyCode::Char(UP_KEY_ALT) => {
-                    if 0 < self.cursor.1 {
-                        self.cursor.1 -= 1;
+            self.message = None;
+            match keys.action(&key_event) {
+                Some(Action::Quit) => return false, // Indicate quit
+                Some(Action::Stop) => self.stop = !self.stop,
+                Some(Action::ToggleView) => self.view = self.view.next(),
+                Some(Action::ToggleHeader) => self.show_header = !self.show_header,
+                Some(Action::ToggleGrid) => self.show_grid = !self.show_grid,
+                Some(Action::ToggleAge) => self.age_colors = !self.age_colors,
+                Some(Action::ToggleDiff) => self.show_diff = !self.show_diff,
+                Some(Action::Analyze) => {
+                    self.message = Some(match self.analyze(ANALYSIS_GENERATIONS) {
+                        Ok(behavior) => format!("Analysis: {behavior}"),
+                        Err(error) => format!("Failed to analyze: {error:#}"),
+                    });
+                }
+                Some(Action::ToggleHeatmap) => {
+                    self.heat = match self.heat {
+                        Some(_) => None,
+                        None => Some(HashMap::new()),
+                    };
+                }
+                Some(Action::Help) => self.show_help = true,
+                // repairs a screen that other programs wrote over, which
+                // drawing only the changes would never touch
+                Some(Action::Redraw) => self.frame = None,
+                Some(Action::ToggleCell) => self.toggle_cell(),
+                // a single generation, whatever the warp, only while paused
+                Some(Action::Step) if self.stop => self.advance(1),
+                Some(Action::Step) => {}
+                Some(Action::StepBack) => self.step_back(),
+                Some(Action::RunFor) => {
+                    if let Err(error) = self.run_for(self.run_for) {
+                        self.message = Some(format!("{error:#}"));
+                    }
+                }
+                Some(Action::FastForward) => self.fast_forward(),
+                Some(Action::Clear) => self.clear(),
+                Some(Action::Randomize) => self.randomize(),
+                // an infinite universe has no edges
+                Some(Action::ToggleWrap) if self.board.universe.bounds().is_some() => {
+                    self.board.edges = match self.board.edges {
+                        Edges::Wrap => Edges::Dead,
+                        Edges::Dead => Edges::Mirror,
+                        Edges::Mirror => Edges::Wrap,
+                    };
+                    self.wrap_cursor = self.board.edges == Edges::Wrap;
+                    self.stable = None;
+                    self.hashes.clear();
+                }
+                Some(Action::Undo) => self.undo(),
+                Some(Action::Redo) => self.redo(),
+                Some(Action::PlacePattern) => {
+                    self.mode = Mode::PickPattern {
+                        query: String::new(),
+                        index: 0,
                     }
                 }
-                KeyCode::Down | KeyCode::Char(DOWN_KEY_ALT) => {
-                    if self.cursor.1 < self.height - 1 {
-                        self.cursor.1 += 1;
+                Some(Action::Select) => {
+                    self.mode = Mode::Select {
+                        anchor: self.cursor,
                     }
                 }
-                KeyCode::Left | KeyCode::Char(LEFT_KEY_ALT) => {
-                    if 0 < self.cursor.0 {
-                        self.cursor.0 -= 1;
+                Some(Action::Paste) => self.start_paste(),
+                Some(Action::Prompt) => {
+                    self.mode = Mode::Prompt {
+                        input: String::new(),
                     }
                 }
-                KeyCode::Right | KeyCode::Char(RIGHT_KEY_ALT) => {
-                    if self.cursor.0 < self.width - 1 {
-                        self.cursor.0 += 1;
+                Some(action @ (Action::Save | Action::SavePlaintext)) => {
+                    let format = if action == Action::Save {
+                        OutputFormat::Rle
+                    } else {
+                        OutputFormat::Cells
+                    };
+                    self.message = Some(match self.save_pattern(format) {
+                        Ok(file_name) => format!("Saved to {file_name}"),
+                        Err(error) => format!("Failed to save: {error:#}"),
+                    });
+                }
+                Some(Action::SavePng) => {
+                    self.message = Some(match self.save_png() {
+                        Ok(file_name) => format!("Saved image to {file_name}"),
+                        Err(error) => format!("Failed to save image: {error:#}"),
+                    });
+                }
+                Some(Action::SaveSession) => {
+                    self.message = Some(match self.save_session(Path::new(SESSION_FILE)) {
+                        Ok(()) => format!("Saved session to {SESSION_FILE}"),
+                        Err(error) => format!("Failed to save session: {error:#}"),
+                    });
+                }
+                Some(Action::LoadSession) => {
+                    self.message = Some(match self.load_session(Path::new(SESSION_FILE)) {
+                        Ok(()) => format!("Loaded session from {SESSION_FILE}"),
+                        Err(error) => format!("Failed to load session: {error:#}"),
+                    });
+                }
+                Some(Action::Faster) => {
+                    self.step_interval = (self.step_interval / 2).max(MIN_STEP_INTERVAL);
+                }
+                Some(Action::Slower) => {
+                    self.step_interval = (self.step_interval * 2).min(MAX_STEP_INTERVAL);
+                }
+                Some(Action::Warp) => self.warp = (self.warp() + 1).min(MAX_WARP),
+                Some(Action::Unwarp) => self.warp = self.warp().saturating_sub(1),
+                Some(Action::Pen) => self.toggle_pen(true),
+                Some(Action::Eraser) => self.toggle_pen(false),
+                Some(action) => {
+                    self.move_cursor(action);
+                    if let Some(alive) = self.pen {
+                        self.board.set(self.cursor.0, self.cursor.1, alive);
                     }
                 }
-                _ => {}
+                None => {}
             }
+        } else if let Event::Mouse(mouse_event) = event {
+            self.handle_mouse(mouse_event);
         }
         true // Indicate continue
     }
+
+    fn move_cursor(&mut self, action: Action) {
+        let (min, max) = match self.board.universe.bounds() {
+            Some((width, height)) => ((0, 0), (width as i64 - 1, height as i64 - 1)),
+            None => ((i64::MIN, i64::MIN), (i64::MAX, i64::MAX)),
+        };
+        match action {
+            Action::Up if self.wrap_cursor && self.cursor.1 == min.1 => self.cursor.1 = max.1,
+            Action::Down if self.wrap_cursor && self.cursor.1 == max.1 => self.cursor.1 = min.1,
+            Action::Left if self.wrap_cursor && self.cursor.0 == min.0 => self.cursor.0 = max.0,
+            Action::Right if self.wrap_cursor && self.cursor.0 == max.0 => self.cursor.0 = min.0,
+            Action::Up if min.1 < self.cursor.1 => self.cursor.1 -= 1,
+            Action::Down if self.cursor.1 < max.1 => self.cursor.1 += 1,
+            Action::Left if min.0 < self.cursor.0 => self.cursor.0 -= 1,
+            Action::Right if self.cursor.0 < max.0 => self.cursor.0 += 1,
+            Action::RowStart => self.cursor.0 = 0,
+            Action::RowEnd => self.cursor.0 = self.width as i64 - 1,
+            Action::PanUp => self.pan((0, -1)),
+            Action::PanDown => self.pan((0, 1)),
+            Action::PanLeft => self.pan((-1, 0)),
+            Action::PanRight => self.pan((1, 0)),
+            Action::Center => {
+                self.cursor = (self.width as i64 / 2, self.height as i64 / 2);
+                if let Ok(size) = terminal::size() {
+                    self.center_viewport(size);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_pattern_picker(&mut self, code: KeyCode) {
+        let Mode::PickPattern { query, index } = &mut self.mode else {
+            return;
+        };
+        let matches = patterns::search(query);
+        let count = matches.len().max(1);
+        match code {
+            KeyCode::Up | KeyCode::Left => *index = (*index + count - 1) % count,
+            KeyCode::Down | KeyCode::Right => *index = (*index + 1) % count,
+            KeyCode::Char(c) => {
+                query.push(c);
+                *index = 0;
+            }
+            KeyCode::Backspace => {
+                query.pop();
+                *index = 0;
+            }
+            KeyCode::Enter => {
+                if let Some(pattern) = matches.get(*index) {
+                    self.mode = Mode::Place {
+                        name: pattern.name,
+                        pattern: pattern.cells(),
+                        paste: false,
+                    };
+                }
+            }
+            KeyCode::Esc => self.mode = Mode::Edit,
+            _ => {}
+        }
+    }
+
+    fn handle_prompt(&mut self, code: KeyCode) {
+        let Mode::Prompt { input } = &mut self.mode else {
+            return;
+        };
+        match code {
+            KeyCode::Char(c) => input.push(c),
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Enter => {
+                if let Mode::Prompt { input } = std::mem::replace(&mut self.mode, Mode::Edit)
+                    && let Err(error) = self.run_command(&input)
+                {
+                    self.message = Some(format!("{error:#}"));
+                }
+            }
+            KeyCode::Esc => self.mode = Mode::Edit,
+            _ => {}
+        }
+    }
+
+    /// Runs a command typed after ':', `x,y` to move the cursor to a cell,
+    /// `rule B/S` to change the rule, `run N` to run N generations and pause
+    /// or `w NAME` to save the pattern, as plaintext for a `.cells` or
+    /// `.txt` name and as RLE otherwise.
+    fn run_command(&mut self, input: &str) -> anyhow::Result<()> {
+        let input = input.trim();
+        if let Some(rule) = input.strip_prefix("rule ") {
+            let rule: Rule = rule.trim().parse()?;
+            if self.board.universe.bounds().is_none() {
+                rule.check_infinite()?;
+            }
+            self.board.rule = rule;
+            self.stable = None;
+            self.hashes.clear();
+            self.message = Some(format!("Rule set to {rule}"));
+            return Ok(());
+        }
+        if let Some(generations) = input.strip_prefix("run ") {
+            let generations: u64 = generations
+                .trim()
+                .parse()
+                .ok()
+                .filter(|&generations| 0 < generations)
+                .with_context(|| format!("invalid number of generations {generations:?}"))?;
+            self.run_for(generations)?;
+            self.message = Some(format!("Running {generations} generations"));
+            return Ok(());
+        }
+        if let Some(name) = input.strip_prefix("w ") {
+            let mut path = PathBuf::from(name.trim());
+            if path.extension().is_none() {
+                path.set_extension("rle");
+            }
+            let is_plaintext = path.extension().is_some_and(|extension| {
+                extension.eq_ignore_ascii_case("cells") || extension.eq_ignore_ascii_case("txt")
+            });
+            let contents = if is_plaintext {
+                let name = path.file_stem().map(|stem| stem.to_string_lossy());
+                self.to_plaintext(name.as_deref())
+            } else {
+                self.to_rle()
+            };
+            std::fs::write(&path, contents)
+                .with_context(|| format!("failed to write {}", path.display()))?;
+            self.message = Some(format!("Saved to {}", path.display()));
+            return Ok(());
+        }
+
+        let Some((x, y)) = input.split_once(',') else {
+            anyhow::bail!("expected x,y, got {input:?}");
+        };
+        let coordinate = |value: &str| {
+            value
+                .trim()
+                .parse::<i64>()
+                .with_context(|| format!("invalid coordinate {value:?}"))
+        };
+        let (x, y) = (coordinate(x)?, coordinate(y)?);
+        if !self.in_field(x, y) {
+            anyhow::bail!(
+                "{x},{y} is outside the {}x{} field",
+                self.width,
+                self.height
+            );
+        }
+        self.cursor = (x, y);
+        Ok(())
+    }
+
+    fn handle_placement(&mut self, key_event: &KeyEvent, keys: &KeyBindings) {
+        let Mode::Place { pattern, .. } = &mut self.mode else {
+            return;
+        };
+        match key_event.code {
+            KeyCode::Char(ROTATE_KEY) => *pattern = patterns::rotate(pattern),
+            KeyCode::Char(ROTATE_BACK_KEY) => *pattern = patterns::rotate_back(pattern),
+            KeyCode::Char(FLIP_KEY) => *pattern = patterns::flip(pattern),
+            KeyCode::Char(FLIP_VERTICALLY_KEY) => *pattern = patterns::flip_vertically(pattern),
+            KeyCode::Enter => {
+                if let Mode::Place { pattern, paste, .. } =
+                    std::mem::replace(&mut self.mode, Mode::Edit)
+                {
+                    if paste {
+                        self.paste(&pattern);
+                    } else {
+                        self.stamp(&pattern, self.cursor);
+                    }
+                }
+            }
+            KeyCode::Esc => self.mode = Mode::Edit,
+            _ => {
+                if let Some(action) = keys.action(key_event) {
+                    self.move_cursor(action);
+                }
+            }
+        }
+    }
+
+    fn handle_selection(&mut self, key_event: &KeyEvent, keys: &KeyBindings) {
+        match key_event.code {
+            KeyCode::Char(c @ (YANK_KEY | CUT_KEY)) => {
+                if let Some(bounds) = self.selection() {
+                    let (left, top, right, bottom) = bounds;
+                    self.clipboard = Some(self.area(bounds));
+                    let size = format!("{}x{}", right - left + 1, bottom - top + 1);
+                    self.message = Some(if c == CUT_KEY {
+                        self.record_edit();
+                        for y in top..=bottom {
+                            for x in left..=right {
+                                self.board.set(x, y, false);
+                            }
+                        }
+                        format!("Cut {size}")
+                    } else {
+                        format!("Yanked {size}")
+                    });
+                }
+                self.mode = Mode::Edit;
+            }
+            KeyCode::Esc => self.mode = Mode::Edit,
+            _ => {
+                if let Some(action) = keys.action(key_event) {
+                    self.move_cursor(action);
+                }
+            }
+        }
+    }
+
+    /// Returns `(left, top, right, bottom)` of the selection, inclusive.
+    fn selection(&self) -> Option<(i64, i64, i64, i64)> {
+        let Mode::Select { anchor } = self.mode else {
+            return None;
+        };
+        Some((
+            anchor.0.min(self.cursor.0),
+            anchor.1.min(self.cursor.1),
+            anchor.0.max(self.cursor.0),
+            anchor.1.max(self.cursor.1),
+        ))
+    }
+
+    /// Previews the clipboard at the cursor, to be pasted like a pattern.
+    fn start_paste(&mut self) {
+        let Some(clipboard) = self.clipboard.clone() else {
+            self.message = Some("Nothing to paste, select with 'V' and yank with 'y'".to_string());
+            return;
+        };
+        self.mode = Mode::Place {
+            name: "the yanked selection",
+            pattern: clipboard,
+            paste: true,
+        };
+    }
+
+    /// Copies a yanked selection onto the field with its top-left corner at
+    /// the cursor, clipping it at the edges.
+    fn paste(&mut self, pattern: &[Vec<bool>]) {
+        self.record_edit();
+        for (dy, row) in pattern.iter().enumerate() {
+            for (dx, &alive) in row.iter().enumerate() {
+                let (x, y) = (self.cursor.0 + dx as i64, self.cursor.1 + dy as i64);
+                if !self.in_field(x, y) {
+                    continue;
+                }
+                match self.paste_mode {
+                    PasteMode::Overwrite => self.board.set(x, y, alive),
+                    PasteMode::Or if alive && !self.board.get(x, y) => self.board.set(x, y, true),
+                    PasteMode::Or => {}
+                }
+            }
+        }
+    }
+
+    /// Returns whether the pattern being placed has a living cell at `(x, y)`.
+    fn is_pending_at(&self, x: i64, y: i64) -> bool {
+        let Mode::Place { pattern, paste, .. } = &self.mode else {
+            return false;
+        };
+        // pasting clips at the edges rather than wrapping
+        let wrap =
+            self.board.edges == Edges::Wrap && self.board.universe.bounds().is_some() && !paste;
+        let offset = |position: i64, origin: i64, size: u16| {
+            let offset = position - origin;
+            let offset = if wrap {
+                offset.rem_euclid(size as i64)
+            } else {
+                offset
+            };
+            usize::try_from(offset).ok()
+        };
+        let (Some(dx), Some(dy)) = (
+            offset(x, self.cursor.0, self.width),
+            offset(y, self.cursor.1, self.height),
+        ) else {
+            return false;
+        };
+        pattern
+            .get(dy)
+            .and_then(|row| row.get(dx))
+            .copied()
+            .unwrap_or(false)
+    }
+
+    fn handle_mouse(&mut self, mouse_event: MouseEvent) {
+        let cells = self.cells_at_screen(mouse_event.column, mouse_event.row);
+        match mouse_event.kind {
+            MouseEventKind::Down(MouseButton::Left) if !cells.is_empty() => {
+                // a whole click-and-drag stroke is undone at once
+                self.record_edit();
+                let alive = !cells.iter().any(|&(x, y)| self.board.get(x, y));
+                for (x, y) in cells {
+                    self.board.set(x, y, alive);
+                }
+            }
+            MouseEventKind::Drag(MouseButton::Left) => {
+                for (x, y) in cells {
+                    self.board.set(x, y, true);
+                }
+            }
+            // the right button erases, clicked or dragged
+            MouseEventKind::Down(MouseButton::Right) if !cells.is_empty() => {
+                self.record_edit();
+                for (x, y) in cells {
+                    self.board.set(x, y, false);
+                }
+            }
+            MouseEventKind::Drag(MouseButton::Right) => {
+                for (x, y) in cells {
+                    self.board.set(x, y, false);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Returns the field cells drawn at a terminal position: one in the
+    /// normal view, every cell of the block in the denser views.
+    fn cells_at_screen(&self, column: u16, row: u16) -> Vec<(i64, i64)> {
+        let Some(row) = row.checked_sub(self.field_top()) else {
+            return Vec::new();
+        };
+        let (cell_width, cell_height) = self.view.cell_size();
+        let (cell_width, cell_height) = (cell_width as i64, cell_height as i64);
+        let left = self.viewport.0 + column as i64 * cell_width;
+        let top = self.viewport.1 + row as i64 * cell_height;
+        (top..top + cell_height)
+            .flat_map(|y| (left..left + cell_width).map(move |x| (x, y)))
+            .filter(|&(x, y)| self.in_field(x, y))
+            .collect()
+    }
 }
 
-fn main() -> anyhow::Result<()> {
-    let mut game = Game::try_new()?;
+/// The open boards, each a game of its own with its field, generation and
+/// cursor. Only the board on screen runs.
+struct Workspace {
+    games: Vec<Game>,
+    active: usize,
+}
 
-    let mut stdout = std::io::stdout();
-    execute!(stdout, cursor::Hide, terminal::EnterAlternateScreen,)?;
-    terminal::enable_raw_mode()?;
+impl Workspace {
+    fn game(&mut self) -> &mut Game {
+        &mut self.games[self.active]
+    }
 
-    loop {
-        game.update()?;
-        if crossterm::event::poll(std::time::Duration::from_millis(200))?
-            && !game.handle_input(crossterm::event::read()?)
+    fn draw(&mut self, keys: &KeyBindings) -> anyhow::Result<()> {
+        let boards = (self.active, self.games.len());
+        self.game().draw(keys, boards)
+    }
+
+    /// Switches or opens boards, and passes every other event on to the
+    /// board on screen. Returns false on quit.
+    fn handle_input(&mut self, event: Event, keys: &KeyBindings) -> bool {
+        if let Event::Resize(..) = event {
+            for game in &mut self.games {
+                game.handle_input(event.clone(), keys);
+            }
+            return true;
+        }
+
+        let game = self.game();
+        if let Event::Key(key_event) = &event
+            && matches!(game.mode, Mode::Edit)
+            && !game.show_help
         {
-            break;
+            let count = self.games.len();
+            match keys.action(key_event) {
+                // the screen shows another board than the one switched to last drew
+                Some(Action::NextBoard) => {
+                    self.active = (self.active + 1) % count;
+                    self.game().frame = None;
+                    return true;
+                }
+                Some(Action::PreviousBoard) => {
+                    self.active = (self.active + count - 1) % count;
+                    self.game().frame = None;
+                    return true;
+                }
+                Some(Action::NewBoard) => {
+                    let game = self.game();
+                    let fresh = game.fresh(game.width, game.height);
+                    self.games.push(fresh);
+                    self.active = count;
+                    return true;
+                }
+                _ => {}
+            }
         }
+        self.game().handle_input(event, keys)
+    }
+}
+
+/// Holds the terminal in raw mode on the alternate screen, and puts it back
+/// when dropped, on errors too. A panic restores it before its message is
+/// printed.
+struct TerminalGuard;
+
+impl TerminalGuard {
+    fn enter() -> anyhow::Result<TerminalGuard> {
+        let default_hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            restore_terminal();
+            default_hook(info);
+        }));
+
+        // from here on, leaving through an error restores the terminal
+        let guard = TerminalGuard;
+        execute!(
+            std::io::stdout(),
+            cursor::Hide,
+            terminal::EnterAlternateScreen,
+            event::EnableMouseCapture,
+        )?;
+        terminal::enable_raw_mode()?;
+        Ok(guard)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        restore_terminal();
+    }
+}
+
+/// Leaves raw mode and the alternate screen, as far as the terminal lets it.
+fn restore_terminal() {
+    let _ = terminal::disable_raw_mode();
+    let _ = execute!(
+        std::io::stdout(),
+        event::DisableMouseCapture,
+        cursor::Show,
+        terminal::LeaveAlternateScreen,
+    );
+}
+
+fn parse_density(s: &str) -> anyhow::Result<f64> {
+    let density: f64 = s.parse()?;
+    if !(0.0..=1.0).contains(&density) {
+        anyhow::bail!("the density must be between 0 and 1, got {density}");
     }
+    Ok(density)
+}
+
+fn parse_size(s: &str) -> anyhow::Result<(u16, u16)> {
+    let Some((width, height)) = s.split_once('x') else {
+        anyhow::bail!("expected WIDTHxHEIGHT, got {s:?}");
+    };
+    let (width, height) = (width.parse::<u16>()?, height.parse::<u16>()?);
+    if width == 0 || height == 0 {
+        anyhow::bail!("the size must be at least 1x1");
+    }
+    Ok((width, height))
+}
 
-    execute!(stdout, cursor::Show, terminal::LeaveAlternateScreen,)?;
-    terminal::disable_raw_mode()?;
+/// Runs random soups on the infinite plane until they settle, reporting
+/// those still active after the cap or with cells escaping the soup's box.
+fn run_soup_search(args: &Args) -> anyhow::Result<()> {
+    let rule = args.rule.unwrap_or(Rule::CONWAY);
+    rule.check_infinite()?;
+    let generations = match args.generations {
+        0 => SOUP_GENERATIONS,
+        generations => generations,
+    };
+    let (width, height) = args.size;
+    let first_seed = args.seed.unwrap_or_else(rand::random);
+    eprintln!("seed {first_seed}");
+
+    let mut interesting = 0;
+    for trial in 0..args.trials {
+        let mut game = Game::new(width, height);
+        game.board.universe = match args.engine {
+            Engine::Cells => Box::new(Sparse::default()),
+            Engine::Hashlife => Box::new(HashLife::default()),
+        };
+        game.board.rule = rule;
+        game.board.neighborhood = args.neighborhood.offsets();
+        game.seed = first_seed.wrapping_add(trial);
+        game.rng = StdRng::seed_from_u64(game.seed);
+        game.fill_random(SOUP_DENSITY);
+
+        while game.generation < generations && game.stable.is_none() {
+            game.step();
+        }
+        if game.stable.is_some() {
+            continue;
+        }
+        // beyond the soup's box by more than its own size
+        let escaped = game
+            .bounding_box()
+            .is_some_and(|(left, top, right, bottom)| {
+                let (width, height) = (width as i64, height as i64);
+                left < -width || top < -height || 2 * width <= right || 2 * height <= bottom
+            });
+        interesting += 1;
+        println!(
+            "seed {}: {} at gen {}, population {}",
+            game.seed,
+            if escaped { "escaped" } else { "still active" },
+            game.generation,
+            game.population()
+        );
+    }
+    eprintln!("{interesting} of {} soups were interesting", args.trials);
     Ok(())
 }
+
+fn run_subcommand(command: &Command) -> anyhow::Result<()> {
+    match command {
+        Command::Simulate {
+            pattern: path,
+            rule,
+            generations,
+            neighborhood,
+            engine,
+            output,
+        } => {
+            let pattern = read_pattern(path)?;
+            let rule = match rule {
+                Some(rule) => *rule,
+                None => read_pattern_rule(path)?.unwrap_or(Rule::CONWAY),
+            };
+            rule.check_infinite()?;
+            let mut game = Game::new(1, 1);
+            game.board.universe = match engine {
+                Engine::Cells => Box::new(Sparse::default()),
+                Engine::Hashlife => Box::new(HashLife::default()),
+            };
+            game.board.rule = rule;
+            game.board.neighborhood = neighborhood.offsets();
+            game.draw_pattern(&pattern, (0, 0));
+            game.board.advance(*generations);
+
+            let rle = game.to_rle();
+            match output {
+                Some(path) => std::fs::write(path, rle)
+                    .with_context(|| format!("failed to write {}", path.display())),
+                None => {
+                    print!("{rle}");
+                    Ok(())
+                }
+            }
+        }
+        Command::Bench {
+            generations, seed, ..
+        } => {
+            bench::run(*generations, *seed);
+            Ok(())
+        }
+    }
+}
+
+fn run_headless(game: &mut Game, args: &Args) -> anyhow::Result<()> {
+    if game.density.is_some() {
+        eprintln!("seed {}", game.seed);
+    }
+    let mut recorder = args
+        .record
+        .as_deref()
+        .map(|path| {
+            export::GifRecorder::create(
+                path,
+                game.width,
+                game.height,
+                args.cell_size,
+                args.image_colors(),
+                args.fps,
+            )
+        })
+        .transpose()?;
+    if let Some(recorder) = &mut recorder {
+        recorder.add_frame(&game.window())?;
+    }
+
+    let generations = match args.frames {
+        Some(frames) => frames - 1,
+        None => args.generations,
+    };
+    let mut overpopulated = game.overpopulated();
+    for _ in 0..generations {
+        if overpopulated {
+            break;
+        }
+        game.step();
+        if let Some(recorder) = &mut recorder {
+            recorder.add_frame(&game.window())?;
+        }
+        overpopulated = game.overpopulated();
+        if args.stop_on_stable && game.stable.is_some() {
+            if let Some(message) = &game.message {
+                eprintln!("{message}");
+            }
+            break;
+        }
+    }
+    if let Some(recorder) = recorder {
+        recorder.finish()?;
+    }
+    if overpopulated {
+        eprintln!(
+            "Population {} exceeded {} at gen {}",
+            game.population(),
+            game.max_population.unwrap_or_default(),
+            game.generation
+        );
+        std::process::exit(OVERPOPULATED_EXIT_CODE);
+    }
+    if args.headless {
+        match args.output_format {
+            OutputFormat::Plaintext => print!("{}", plaintext::encode(&game.window())),
+            OutputFormat::Cells => print!("{}", game.to_plaintext(None)),
+            OutputFormat::Rle => print!("{}", game.to_rle()),
+        }
+    }
+    export_png(game, args)
+}
+
+fn export_png(game: &Game, args: &Args) -> anyhow::Result<()> {
+    match &args.export_png {
+        Some(path) => export::save_png(&game.snapshot()?, game.cell_size, game.image_colors, path),
+        None => Ok(()),
+    }
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+
+    // the dense engine only goes parallel when the pool has several threads
+    let threads = match args.command {
+        Some(Command::Bench { threads, .. }) => threads,
+        _ => args.threads,
+    };
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(threads.into())
+        .build_global()?;
+
+    if let Some(command) = &args.command {
+        return run_subcommand(command);
+    }
+    if args.soup_search {
+        return run_soup_search(&args);
+    }
+
+    let path = args.input.as_deref().or(args.pattern.as_deref());
+    let pattern = match &args.builtin {
+        Some(name) => Some(
+            patterns::find(name)
+                .with_context(|| format!("no built-in pattern matches {name:?}"))?
+                .cells(),
+        ),
+        None => path.map(read_pattern).transpose()?,
+    };
+    let rule = match args.rule {
+        Some(rule) => rule,
+        None => path
+            .map(read_pattern_rule)
+            .transpose()?
+            .flatten()
+            .unwrap_or(Rule::CONWAY),
+    };
+
+    // unless given explicitly, the field fills the terminal and grows to fit the pattern
+    let batch = args.headless || args.record.is_some() || args.analyze;
+    let (terminal_width, terminal_height) = if batch {
+        (1, 1)
+    } else {
+        let (width, height) = terminal::size()?;
+        if width < MIN_TERMINAL_SIZE.0 || height < MIN_TERMINAL_SIZE.1 {
+            anyhow::bail!(
+                "the terminal is {width}x{height} but at least {}x{} is needed",
+                MIN_TERMINAL_SIZE.0,
+                MIN_TERMINAL_SIZE.1
+            );
+        }
+        (width, height)
+    };
+    let pattern_width = pattern
+        .as_ref()
+        .and_then(|pattern| pattern.first())
+        .map_or(0, Vec::len);
+    let pattern_height = pattern.as_ref().map_or(0, Vec::len);
+    let width = args
+        .width
+        .unwrap_or(terminal_width.max(pattern_width.min(u16::MAX as usize) as u16));
+    let height = args
+        .height
+        .unwrap_or(terminal_height.max(pattern_height.min(u16::MAX as usize) as u16));
+
+    let mut game = Game::new(width, height);
+    let topology = if args.infinite {
+        Topology::Infinite
+    } else {
+        args.topology
+    };
+    match topology {
+        Topology::Torus => {}
+        Topology::Bounded => {
+            game.board.edges = Edges::Dead;
+            game.wrap_cursor = false;
+        }
+        Topology::Mirror => {
+            game.board.edges = Edges::Mirror;
+            game.wrap_cursor = false;
+        }
+        Topology::Infinite => {}
+    }
+    if args.engine == Engine::Hashlife || matches!(topology, Topology::Infinite) {
+        rule.check_infinite()?;
+        game.board.universe = match args.engine {
+            Engine::Cells => Box::new(Sparse::default()),
+            Engine::Hashlife => {
+                if args.immigration {
+                    anyhow::bail!("HashLife cannot play the Immigration variant");
+                }
+                Box::new(HashLife::default())
+            }
+        };
+    }
+    game.board.rule = rule;
+    game.board.neighborhood = args.neighborhood.offsets();
+    game.immigration = args.immigration;
+    game.paste_mode = args.paste_mode;
+    game.glyphs = (args.alive_char, args.dead_char);
+    game.theme = args.theme.theme();
+    game.true_color = std::env::var("COLORTERM")
+        .is_ok_and(|colorterm| colorterm == "truecolor" || colorterm == "24bit");
+    game.show_seam = args.seam;
+    if let Some(grid) = args.grid {
+        game.show_grid = true;
+        game.grid_spacing = grid;
+    }
+    game.fast_forward = args.fast_forward;
+    game.step_interval = Duration::from_millis(args.speed);
+    game.max_population = args.max_population;
+    if let Some(run_for) = args.run_for {
+        game.run_for = run_for;
+        game.target = Some(run_for);
+    }
+    if let Some(seed) = args.seed {
+        game.seed = seed;
+        game.rng = StdRng::seed_from_u64(seed);
+    }
+    match args.random {
+        Some(density) => game.fill_random(density),
+        None => game.apply_fill(args.fill),
+    }
+    if let Some(pattern) = &pattern {
+        game.place_centered(pattern)?;
+    }
+    for placement in &args.place {
+        let (x, y) = placement.position;
+        if !game.in_field(x, y) {
+            anyhow::bail!(
+                "cannot place {} at {x},{y}: the field is only {}x{}",
+                placement.source,
+                game.width,
+                game.height
+            );
+        }
+        let pattern = placement
+            .pattern()
+            .with_context(|| format!("cannot place {}", placement.source))?;
+        game.draw_pattern(&pattern, placement.position);
+    }
+
+    game.report = args.report.clone();
+    game.cell_size = args.cell_size;
+    game.image_colors = args.image_colors();
+    if args.analyze {
+        let generations = match args.generations {
+            0 => ANALYSIS_GENERATIONS,
+            generations => generations,
+        };
+        println!("{}", game.analyze(generations)?);
+        return Ok(());
+    }
+    if batch {
+        return run_headless(&mut game, &args);
+    }
+
+    let keys = KeyBindings::load()?;
+    // a field grown to fit the pattern keeps its size
+    game.fit_terminal = (width, height) == (terminal_width, terminal_height)
+        && args.width.is_none()
+        && args.height.is_none();
+    if pattern.is_some() {
+        game.fit_to_content(terminal::size()?);
+    }
+    game.rewind_length = args.rewind;
+
+    let terminal = TerminalGuard::enter()?;
+
+    let mut workspace = Workspace {
+        games: vec![game],
+        active: 0,
+    };
+    // input is handled as it arrives, while generations keep to the step interval
+    let mut next_step = Instant::now() + workspace.game().step_interval;
+    let mut redraw = true;
+    loop {
+        let game = workspace.game();
+        if !game.stop && next_step <= Instant::now() {
+            game.step();
+            next_step = Instant::now() + game.step_interval;
+            redraw = true;
+        }
+        if redraw {
+            workspace.draw(&keys)?;
+            redraw = false;
+        }
+
+        let timeout = if workspace.game().stop {
+            PAUSED_POLL_INTERVAL
+        } else {
+            next_step
+                .saturating_duration_since(Instant::now())
+                .min(POLL_INTERVAL)
+        };
+        if crossterm::event::poll(timeout)? {
+            if !workspace.handle_input(crossterm::event::read()?, &keys) {
+                break;
+            }
+            redraw = true;
+        }
+    }
+
+    drop(terminal);
+
+    export_png(workspace.game(), &args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game_with(width: u16, height: u16, cells: &[(i64, i64)]) -> Game {
+        let mut game = Game::new(width, height);
+        for &(x, y) in cells {
+            game.board.set(x, y, true);
+        }
+        game
+    }
+
+    const BLINKER: [(i64, i64); 3] = [(1, 2), (2, 2), (3, 2)];
+    const GLIDER: [(i64, i64); 5] = [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)];
+
+    #[test]
+    fn stepping_back_goes_through_the_rewind_ring() {
+        let mut game = game_with(16, 16, &GLIDER);
+        game.rewind_length = 3;
+        let before: Vec<Vec<Vec<bool>>> = (0..=5)
+            .map(|_| {
+                let window = game.window();
+                game.step();
+                window
+            })
+            .collect();
+        assert_eq!(game.generation, 6);
+        assert_eq!(game.rewind.len(), 3);
+
+        for generation in (3..6).rev() {
+            game.step_back();
+            assert_eq!(game.generation, generation);
+            assert_eq!(game.window(), before[generation as usize]);
+        }
+        // older generations are gone
+        game.step_back();
+        assert_eq!(game.generation, 3);
+    }
+
+    #[test]
+    fn running_for_a_while_pauses_at_the_target() {
+        let mut game = game_with(16, 16, &GLIDER);
+        game.warp = 2;
+        game.run_for(10).unwrap();
+        assert!(!game.stop);
+        while !game.stop {
+            game.step();
+        }
+        // the last step is cut short to land on the target
+        assert_eq!(game.generation, 10);
+        assert_eq!(game.target, None);
+        assert_eq!(game.message.as_deref(), Some("Paused at target 10"));
+
+        game.generation = u64::MAX - 1;
+        assert!(game.run_for(2).is_err());
+        assert!(game.run_command("run 5").is_err());
+    }
+
+    #[test]
+    fn oscillators_pause_with_their_period() {
+        let mut game = game_with(8, 8, &BLINKER);
+        game.stop = false;
+        while !game.stop {
+            game.step();
+        }
+        assert_eq!(game.generation, 2);
+        assert_eq!(game.stable, Some((0, 2)));
+        assert_eq!(
+            game.message.as_deref(),
+            Some("Stabilized at generation 0 with period 2")
+        );
+
+        // stepping on keeps the generation it settled at
+        game.advance(1);
+        assert_eq!(game.stable, Some((0, 2)));
+
+        let mut game = game_with(8, 8, &[(0, 0)]);
+        game.step();
+        game.step();
+        assert_eq!(game.message.as_deref(), Some("Died out at gen 1"));
+    }
+
+    #[test]
+    fn sessions_load_as_they_were_saved() {
+        let path = std::env::temp_dir().join(format!(
+            "game-of-life-test-session-{}.json",
+            std::process::id()
+        ));
+        let mut game = game_with(12, 9, &GLIDER);
+        game.board.rule = "B36/S23".parse().unwrap();
+        game.board.edges = Edges::Dead;
+        game.generation = 42;
+        game.cursor = (5, 7);
+        game.save_session(&path).unwrap();
+
+        let mut loaded = Game::new(3, 3);
+        loaded.load_session(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!((loaded.width, loaded.height), (12, 9));
+        assert_eq!(loaded.window(), game.window());
+        assert_eq!(loaded.board.rule, game.board.rule);
+        assert_eq!(loaded.board.edges, Edges::Dead);
+        assert_eq!(loaded.generation, 42);
+        assert_eq!(loaded.cursor, (5, 7));
+    }
+
+    #[test]
+    fn pasting_overwrites_or_adds_to_the_field() {
+        let pattern = [vec![true, false], vec![false, true]];
+        let mut game = game_with(4, 4, &[(2, 1)]);
+        game.cursor = (1, 1);
+        game.paste(&pattern);
+        assert_eq!(
+            game.board.universe.live_cells().len(),
+            2,
+            "the dead pasted cell replaces (2, 1)"
+        );
+        assert!(game.board.get(1, 1) && game.board.get(2, 2));
+
+        let mut game = game_with(4, 4, &[(2, 1)]);
+        game.cursor = (1, 1);
+        game.paste_mode = PasteMode::Or;
+        game.paste(&pattern);
+        assert!(game.board.get(2, 1));
+        // clipped at the edges
+        game.cursor = (3, 3);
+        game.paste(&pattern);
+        assert!(game.board.get(3, 3));
+        assert_eq!(game.population(), 4);
+    }
+
+    #[test]
+    fn prompt_commands_change_the_game() {
+        let mut game = game_with(8, 8, &BLINKER);
+        game.run_command("rule B36/S23").unwrap();
+        assert_eq!(game.board.rule.to_string(), "B36/S23");
+        assert!(game.run_command("rule nonsense").is_err());
+
+        game.run_command("3,4").unwrap();
+        assert_eq!(game.cursor, (3, 4));
+        assert!(game.run_command("30,4").is_err());
+    }
+}
@@ -0,0 +1,125 @@
+//! Built-in library of well-known patterns, bundled from `patterns/`.
+
+use crate::rle;
+
+pub struct Pattern {
+    pub name: &'static str,
+    rle: &'static str,
+}
+
+impl Pattern {
+    pub fn cells(&self) -> Vec<Vec<bool>> {
+        rle::parse(self.rle).expect("built-in patterns are valid RLE")
+    }
+}
+
+pub const PATTERNS: &[Pattern] = &[
+    Pattern {
+        name: "glider",
+        rle: include_str!("../patterns/glider.rle"),
+    },
+    Pattern {
+        name: "lightweight spaceship",
+        rle: include_str!("../patterns/lightweight-spaceship.rle"),
+    },
+    Pattern {
+        name: "block",
+        rle: include_str!("../patterns/block.rle"),
+    },
+    Pattern {
+        name: "blinker",
+        rle: include_str!("../patterns/blinker.rle"),
+    },
+    Pattern {
+        name: "toad",
+        rle: include_str!("../patterns/toad.rle"),
+    },
+    Pattern {
+        name: "beacon",
+        rle: include_str!("../patterns/beacon.rle"),
+    },
+    Pattern {
+        name: "pulsar",
+        rle: include_str!("../patterns/pulsar.rle"),
+    },
+    Pattern {
+        name: "pentadecathlon",
+        rle: include_str!("../patterns/pentadecathlon.rle"),
+    },
+    Pattern {
+        name: "R-pentomino",
+        rle: include_str!("../patterns/r-pentomino.rle"),
+    },
+    Pattern {
+        name: "diehard",
+        rle: include_str!("../patterns/diehard.rle"),
+    },
+    Pattern {
+        name: "acorn",
+        rle: include_str!("../patterns/acorn.rle"),
+    },
+    Pattern {
+        name: "Gosper glider gun",
+        rle: include_str!("../patterns/gosper-glider-gun.rle"),
+    },
+];
+
+/// Returns the patterns whose name contains the letters of `query` in order,
+/// ignoring case, spaces and punctuation; names containing the query as a
+/// whole come first.
+pub fn search(query: &str) -> Vec<&'static Pattern> {
+    let query = normalize(query);
+    let (mut matches, fuzzy): (Vec<_>, Vec<_>) = PATTERNS
+        .iter()
+        .filter(|pattern| is_subsequence(&query, &normalize(pattern.name)))
+        .partition(|pattern| normalize(pattern.name).contains(&query));
+    matches.extend(fuzzy);
+    matches
+}
+
+/// Returns the best match for `name`, see [`search`].
+pub fn find(name: &str) -> Option<&'static Pattern> {
+    search(name).into_iter().next()
+}
+
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+fn is_subsequence(needle: &str, haystack: &str) -> bool {
+    let mut haystack = haystack.chars();
+    needle.chars().all(|c| haystack.any(|h| h == c))
+}
+
+/// Rotates a pattern 90 degrees clockwise.
+pub fn rotate(pattern: &[Vec<bool>]) -> Vec<Vec<bool>> {
+    let width = pattern.first().map_or(0, Vec::len);
+    (0..width)
+        .map(|x| pattern.iter().rev().map(|row| row[x]).collect())
+        .collect()
+}
+
+/// Rotates a pattern 90 degrees counter-clockwise.
+pub fn rotate_back(pattern: &[Vec<bool>]) -> Vec<Vec<bool>> {
+    let width = pattern.first().map_or(0, Vec::len);
+    (0..width)
+        .rev()
+        .map(|x| pattern.iter().map(|row| row[x]).collect())
+        .collect()
+}
+
+/// Mirrors a pattern horizontally.
+pub fn flip(pattern: &[Vec<bool>]) -> Vec<Vec<bool>> {
+    pattern
+        .iter()
+        .map(|row| row.iter().rev().copied().collect())
+        .collect()
+}
+
+/// Mirrors a pattern vertically.
+pub fn flip_vertically(pattern: &[Vec<bool>]) -> Vec<Vec<bool>> {
+    pattern.iter().rev().cloned().collect()
+}
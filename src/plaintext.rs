@@ -0,0 +1,111 @@
+//! Parser and encoder for the plaintext `.cells` pattern format.
+//!
+//! Lines starting with `!` are comments, `.` is a dead cell and `O` (or `*`)
+//! is a living cell. Rows shorter than the widest row are padded with dead
+//! cells so the result is always rectangular.
+
+use anyhow::bail;
+
+pub fn parse(input: &str) -> anyhow::Result<Vec<Vec<bool>>> {
+    let mut rows = Vec::new();
+
+    for (index, line) in input.lines().enumerate() {
+        let line = line.trim_end();
+        if line.starts_with('!') {
+            continue;
+        }
+
+        let mut row = Vec::with_capacity(line.len());
+        for c in line.chars() {
+            match c {
+                '.' => row.push(false),
+                'O' | '*' => row.push(true),
+                _ => bail!("line {}: unexpected character {c:?}", index + 1),
+            }
+        }
+        rows.push(row);
+    }
+
+    let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+    for row in &mut rows {
+        row.resize(width, false);
+    }
+
+    Ok(rows)
+}
+
+pub fn encode(pattern: &[Vec<bool>]) -> String {
+    let mut output = String::new();
+    for row in pattern {
+        output.extend(row.iter().map(|&alive| if alive { 'O' } else { '.' }));
+        output.push('\n');
+    }
+    output
+}
+
+/// Encodes a pattern compactly, leaving out the dead cells at the end of
+/// each row and the dead rows at the end, after a `!Name:` comment if
+/// `name` is given.
+pub fn encode_trimmed(pattern: &[Vec<bool>], name: Option<&str>) -> String {
+    let mut output = String::new();
+    if let Some(name) = name {
+        output.push_str(&format!("!Name: {name}\n"));
+    }
+    let height = pattern
+        .iter()
+        .rposition(|row| row.contains(&true))
+        .map_or(0, |last| last + 1);
+    for row in &pattern[..height] {
+        let width = row
+            .iter()
+            .rposition(|&alive| alive)
+            .map_or(0, |last| last + 1);
+        output.extend(
+            row[..width]
+                .iter()
+                .map(|&alive| if alive { 'O' } else { '.' }),
+        );
+        output.push('\n');
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rows_are_padded_and_comments_skipped() {
+        let pattern = parse("!Name: Glider\n.O\n..*\nOOO\n!\n").unwrap();
+        assert_eq!(
+            pattern,
+            [
+                [false, true, false],
+                [false, false, true],
+                [true, true, true]
+            ]
+        );
+        assert_eq!(parse(&encode(&pattern)).unwrap(), pattern);
+        assert!(parse(".O\n.x\n").is_err());
+    }
+
+    #[test]
+    fn trimmed_patterns_leave_out_trailing_dead_cells() {
+        let pattern = [
+            vec![false, true, false, false],
+            vec![false, false, false, false],
+            vec![true, false, true, false],
+            vec![false, false, false, false],
+        ];
+        let trimmed = encode_trimmed(&pattern, Some("Sample"));
+        assert_eq!(trimmed, "!Name: Sample\n.O\n\nO.O\n");
+        assert_eq!(
+            parse(&trimmed).unwrap(),
+            [
+                [false, true, false],
+                [false, false, false],
+                [true, false, true]
+            ]
+        );
+    }
+}
@@ -0,0 +1,395 @@
+//! Parser for the Run Length Encoded (`.rle`) pattern format.
+//!
+//! An RLE file has an optional `x = 3, y = 3, rule = B3/S23` header followed
+//! by rows made of `b` (dead) and `o` (alive) tags, each optionally preceded
+//! by a run count. `$` ends a row and `!` ends the pattern. Lines starting
+//! with `#` are comments.
+
+use anyhow::{Context, bail};
+
+use crate::rule::Rule;
+
+// the widest and tallest field a pattern can fill
+const MAX_SIZE: usize = u16::MAX as usize;
+
+pub fn parse(input: &str) -> anyhow::Result<Vec<Vec<bool>>> {
+    let mut size: Option<(usize, usize)> = None;
+    let mut in_body = false;
+    let mut rows: Vec<Vec<bool>> = vec![Vec::new()];
+    let mut count: Option<usize> = None;
+    let mut finished = false;
+
+    for (index, line) in input.lines().enumerate() {
+        let line_number = index + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if !in_body && line.starts_with('x') {
+            size = Some(
+                parse_header(line)
+                    .with_context(|| format!("line {line_number}: invalid header"))?,
+            );
+            in_body = true;
+            continue;
+        }
+        in_body = true;
+
+        for c in line.chars() {
+            match c {
+                '0'..='9' => {
+                    let digit = c as usize - '0' as usize;
+                    count = Some(
+                        count
+                            .unwrap_or(0)
+                            .checked_mul(10)
+                            .and_then(|n| n.checked_add(digit))
+                            .with_context(|| format!("line {line_number}: run count overflow"))?,
+                    );
+                }
+                'b' | 'o' => {
+                    let run = count.take().unwrap_or(1);
+                    let row = rows.last_mut().expect("rows is never empty");
+                    if MAX_SIZE - row.len() < run {
+                        bail!("line {line_number}: row is wider than the biggest field");
+                    }
+                    row.extend(std::iter::repeat_n(c == 'o', run));
+                    if let Some((width, _)) = size
+                        && width < row.len()
+                    {
+                        bail!("line {line_number}: row is wider than x = {width} from the header");
+                    }
+                }
+                '$' => {
+                    let run = count.take().unwrap_or(1);
+                    if MAX_SIZE - rows.len() < run {
+                        bail!("line {line_number}: pattern is taller than the biggest field");
+                    }
+                    rows.extend(std::iter::repeat_n(Vec::new(), run));
+                    if let Some((_, height)) = size
+                        && height < rows.len()
+                    {
+                        bail!(
+                            "line {line_number}: pattern is taller than y = {height} from the header"
+                        );
+                    }
+                }
+                '!' => {
+                    if count.is_some() {
+                        bail!("line {line_number}: run count is not followed by a tag");
+                    }
+                    finished = true;
+                    break;
+                }
+                c if c.is_whitespace() => {}
+                _ => bail!("line {line_number}: unexpected character {c:?}"),
+            }
+        }
+
+        if finished {
+            break;
+        }
+    }
+
+    if !finished {
+        bail!("pattern is not terminated by '!'");
+    }
+
+    let (width, height) = size.unwrap_or_default();
+    let width = width.max(rows.iter().map(Vec::len).max().unwrap_or(0));
+    if width == 0 {
+        return Ok(Vec::new());
+    }
+    rows.resize(height.max(rows.len()), Vec::new());
+    for row in &mut rows {
+        row.resize(width, false);
+    }
+
+    Ok(rows)
+}
+
+/// Reads the rule from the `rule = B3/S23` entry of the header, if any.
+pub fn rule(input: &str) -> anyhow::Result<Option<Rule>> {
+    let header = input
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with('#'));
+    let Some(header) = header.filter(|line| line.starts_with('x')) else {
+        return Ok(None);
+    };
+    header_entries(header)?
+        .into_iter()
+        .find(|&(key, _)| key == "rule")
+        .map(|(_, value)| value.parse())
+        .transpose()
+}
+
+/// Splits a header into its `key = value` entries. The value of a rule ends
+/// at a `:`, leaving out the grid of Golly's `rule = B3/S23:T20,20`, commas
+/// included.
+fn header_entries(line: &str) -> anyhow::Result<Vec<(&str, &str)>> {
+    let mut entries = Vec::new();
+    let mut in_grid = false;
+    for entry in line.split(',') {
+        let Some((key, value)) = entry.split_once('=') else {
+            if in_grid {
+                continue;
+            }
+            bail!("expected `key = value`, found {:?}", entry.trim());
+        };
+        let (key, mut value) = (key.trim(), value.trim());
+        in_grid = false;
+        if key == "rule"
+            && let Some((rule, _)) = value.split_once(':')
+        {
+            value = rule.trim();
+            in_grid = true;
+        }
+        entries.push((key, value));
+    }
+    Ok(entries)
+}
+
+fn parse_header(line: &str) -> anyhow::Result<(usize, usize)> {
+    let mut width = None;
+    let mut height = None;
+
+    for (key, value) in header_entries(line)? {
+        match key {
+            "x" => width = Some(value.parse().context("x is not a number")?),
+            "y" => height = Some(value.parse().context("y is not a number")?),
+            _ => {}
+        }
+    }
+
+    let (width, height) = (
+        width.context("missing x in header")?,
+        height.context("missing y in header")?,
+    );
+    if MAX_SIZE < width || MAX_SIZE < height {
+        bail!("pattern is {width}x{height}, larger than the biggest field");
+    }
+    Ok((width, height))
+}
+
+/// Encodes a pattern as RLE, run-encoding each row and collapsing blank rows
+/// into a single `n$` run. Body lines are wrapped at 70 characters.
+pub fn encode(pattern: &[Vec<bool>], rule: &Rule) -> String {
+    let height = pattern.len();
+    let width = pattern.first().map_or(0, Vec::len);
+
+    let mut tokens = Vec::new();
+    let mut pending_rows = 0;
+    for (y, row) in pattern.iter().enumerate() {
+        if 0 < y {
+            pending_rows += 1;
+        }
+
+        let runs = runs(row);
+        if runs.is_empty() {
+            continue;
+        }
+        if 0 < pending_rows {
+            tokens.push(token(pending_rows, '$'));
+            pending_rows = 0;
+        }
+        tokens.extend(runs.into_iter().map(|(run, alive)| token(run, tag(alive))));
+    }
+    finish(width as u64, height as u64, tokens, rule)
+}
+
+/// Encodes living cells like [`encode`] encodes their bounding box, from the
+/// cells alone, so that a sparse pattern spread over a huge area costs no
+/// more than its cells.
+pub fn encode_cells(cells: &[(i64, i64)], rule: &Rule) -> String {
+    let mut cells = cells.to_vec();
+    cells.sort_unstable_by_key(|&(x, y)| (y, x));
+    let (Some(left), Some(right), Some(&(_, top)), Some(&(_, bottom))) = (
+        cells.iter().map(|&(x, _)| x).min(),
+        cells.iter().map(|&(x, _)| x).max(),
+        cells.first(),
+        cells.last(),
+    ) else {
+        return finish(0, 0, Vec::new(), rule);
+    };
+
+    let mut tokens = Vec::new();
+    // where the next cell would continue the current run of living cells
+    let (mut x, mut y) = (left, top);
+    let mut alive = 0;
+    for (cell_x, cell_y) in cells {
+        if (cell_x, cell_y) == (x, y) {
+            alive += 1;
+            x += 1;
+            continue;
+        }
+        if 0 < alive {
+            tokens.push(token(alive, 'o'));
+        }
+        if y < cell_y {
+            tokens.push(token((cell_y - y) as usize, '$'));
+            (x, y) = (left, cell_y);
+        }
+        if x < cell_x {
+            tokens.push(token((cell_x - x) as usize, 'b'));
+        }
+        alive = 1;
+        x = cell_x + 1;
+    }
+    tokens.push(token(alive, 'o'));
+
+    let width = right.abs_diff(left) + 1;
+    let height = bottom.abs_diff(top) + 1;
+    finish(width, height, tokens, rule)
+}
+
+/// Writes the header and the tokens ending in `!`, wrapped at 70 characters.
+fn finish(width: u64, height: u64, mut tokens: Vec<String>, rule: &Rule) -> String {
+    tokens.push("!".to_string());
+
+    let mut output = format!("x = {width}, y = {height}, rule = {rule}\n");
+    let mut line_length = 0;
+    for token in tokens {
+        if 70 < line_length + token.len() {
+            output.push('\n');
+            line_length = 0;
+        }
+        line_length += token.len();
+        output.push_str(&token);
+    }
+    output.push('\n');
+
+    output
+}
+
+/// Splits a row into `(length, alive)` runs, dropping trailing dead cells.
+fn runs(row: &[bool]) -> Vec<(usize, bool)> {
+    let mut runs: Vec<(usize, bool)> = Vec::new();
+    for &alive in row {
+        match runs.last_mut() {
+            Some((run, last)) if *last == alive => *run += 1,
+            _ => runs.push((1, alive)),
+        }
+    }
+    if runs.last().is_some_and(|&(_, alive)| !alive) {
+        runs.pop();
+    }
+    runs
+}
+
+fn tag(alive: bool) -> char {
+    if alive { 'o' } else { 'b' }
+}
+
+fn token(run: usize, tag: char) -> String {
+    if run == 1 {
+        tag.to_string()
+    } else {
+        format!("{run}{tag}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{Rng, SeedableRng, rngs::StdRng};
+
+    use super::*;
+
+    fn body(rle: &str) -> String {
+        rle.lines().skip(1).collect()
+    }
+
+    #[test]
+    fn encoded_patterns_parse_back() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..50 {
+            let (width, height) = (rng.random_range(1..120), rng.random_range(1..30));
+            let density = rng.random_range(0.0..0.5);
+            let pattern: Vec<Vec<bool>> = (0..height)
+                .map(|_| (0..width).map(|_| rng.random_bool(density)).collect())
+                .collect();
+            let rle = encode(&pattern, &Rule::CONWAY);
+            assert!(rle.lines().all(|line| line.len() <= 70), "{rle}");
+            assert_eq!(parse(&rle).unwrap(), pattern, "{rle}");
+        }
+    }
+
+    #[test]
+    fn blank_rows_collapse_into_one_run() {
+        let row = |alive| vec![alive, false, alive];
+        let pattern = [row(true), row(false), row(false), row(true), row(false)];
+        let rle = encode(&pattern, &Rule::CONWAY);
+        assert_eq!(rle.lines().next(), Some("x = 3, y = 5, rule = B3/S23"));
+        assert_eq!(body(&rle), "obo3$obo!");
+        assert_eq!(parse(&rle).unwrap(), pattern);
+    }
+
+    #[test]
+    fn runs_continue_across_wrapped_lines() {
+        let pattern = parse("#C a comment\nx = 12, y = 2\n1\n2o$\n   b\n3o\n!").unwrap();
+        assert_eq!(pattern[0], [true; 12]);
+        assert_eq!(
+            pattern[1],
+            [false, true, true, true]
+                .into_iter()
+                .chain([false; 8])
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn patterns_must_fit_their_header() {
+        assert!(parse("x = 2, y = 1\n3o!").is_err());
+        assert!(parse("x = 3, y = 1\no$o!").is_err());
+        assert!(parse("x = 3\n3o!").is_err());
+        assert!(parse("x = 3, y = 1\n3o").is_err());
+        assert!(parse("x = 3, y = 1\n3o2!").is_err());
+        // sizes beyond the biggest field fail before anything is allocated
+        assert!(parse("x = 4000000000, y = 1\no!").is_err());
+        assert!(parse("999999999o!").is_err());
+        assert!(parse("o999999999$o!").is_err());
+        // a header larger than the cells pads them with dead ones
+        assert_eq!(
+            parse("x = 2, y = 2\no!").unwrap(),
+            [[true, false], [false, false]]
+        );
+    }
+
+    #[test]
+    fn cells_encode_like_their_bounding_box() {
+        let pattern = parse("x = 6, y = 6\nbo$4bo$$2o2bo2$5bo!").unwrap();
+        let cells: Vec<(i64, i64)> = pattern
+            .iter()
+            .enumerate()
+            .flat_map(|(y, row)| {
+                row.iter()
+                    .enumerate()
+                    .filter(|&(_, &alive)| alive)
+                    .map(move |(x, _)| (x as i64 - 3, y as i64 + 7))
+            })
+            .collect();
+        assert_eq!(
+            encode_cells(&cells, &Rule::CONWAY),
+            encode(&pattern, &Rule::CONWAY)
+        );
+        assert_eq!(
+            encode_cells(&[], &Rule::CONWAY),
+            "x = 0, y = 0, rule = B3/S23\n!\n"
+        );
+    }
+
+    #[test]
+    fn header_rules_are_read_in_every_notation() {
+        let highlife = "B36/S23".parse().unwrap();
+        assert_eq!(
+            rule("x = 3, y = 1, rule = 23/36\n3o!").unwrap(),
+            Some(highlife)
+        );
+        let golly = "#N Blinker\nx = 3, y = 1, rule = B36/S23:T20,20\n3o!";
+        assert_eq!(rule(golly).unwrap(), Some(highlife));
+        assert_eq!(parse(golly).unwrap(), [[true; 3]]);
+        assert_eq!(rule("x = 3, y = 1\n3o!").unwrap(), None);
+        assert!(rule("x = 3, y = 1, rule = B3/S23/G4\n3o!").is_err());
+    }
+}
@@ -0,0 +1,94 @@
+//! Life-like rules in `B.../S...` notation.
+
+use std::{fmt, str::FromStr};
+
+use anyhow::bail;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rule {
+    // indexed by the number of living neighbors
+    pub birth: [bool; 9],
+    pub survival: [bool; 9],
+}
+
+impl Rule {
+    pub const CONWAY: Rule = Rule {
+        birth: [false, false, false, true, false, false, false, false, false],
+        survival: [false, false, true, true, false, false, false, false, false],
+    };
+
+    pub fn next_state(&self, alive: bool, live_neighbors: usize) -> bool {
+        let counts = if alive { &self.survival } else { &self.birth };
+        counts.get(live_neighbors).copied().unwrap_or(false)
+    }
+
+    /// Fails for a rule giving birth to cells without living neighbors,
+    /// which would fill the infinite plane in a single generation.
+    pub fn check_infinite(&self) -> anyhow::Result<()> {
+        if self.birth[0] {
+            bail!("rule {self} would fill the infinite plane");
+        }
+        Ok(())
+    }
+}
+
+impl Default for Rule {
+    fn default() -> Self {
+        Rule::CONWAY
+    }
+}
+
+impl FromStr for Rule {
+    type Err = anyhow::Error;
+
+    /// Parses rules such as `B3/S23` or `S23/B36`, case-insensitively, and
+    /// rules in the older survival/birth notation such as `23/3`.
+    fn from_str(s: &str) -> anyhow::Result<Rule> {
+        let mut birth = None;
+        let mut survival = None;
+
+        let s = s.trim();
+        let letters = s.contains(|c: char| c.is_ascii_alphabetic());
+        for (index, part) in s.split('/').enumerate() {
+            let mut chars = part.chars();
+            let counts = match (letters, index) {
+                (false, 0) => &mut survival,
+                (false, 1) => &mut birth,
+                _ => match chars.next().map(|c| c.to_ascii_uppercase()) {
+                    Some('B') => &mut birth,
+                    Some('S') => &mut survival,
+                    _ => bail!("invalid rule {s:?}: expected B.../S..."),
+                },
+            };
+            if counts.is_some() {
+                bail!("invalid rule {s:?}: {part:?} is given twice");
+            }
+
+            let mut parsed = [false; 9];
+            for c in chars {
+                match c.to_digit(10) {
+                    Some(count @ 0..=8) => parsed[count as usize] = true,
+                    _ => bail!("invalid rule {s:?}: {c:?} is not a neighbor count"),
+                }
+            }
+            *counts = Some(parsed);
+        }
+
+        match (birth, survival) {
+            (Some(birth), Some(survival)) => Ok(Rule { birth, survival }),
+            _ => bail!("invalid rule {s:?}: expected B.../S..."),
+        }
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let counts = |counts: &[bool; 9]| -> String {
+            (0..9)
+                .filter(|&count| counts[count])
+                .map(|count| char::from(b'0' + count as u8))
+                .collect()
+        };
+        write!(f, "B{}/S{}", counts(&self.birth), counts(&self.survival))
+    }
+}
@@ -0,0 +1,38 @@
+//! JSON snapshots of the whole game state, as opposed to the bare patterns
+//! written by the RLE exporter.
+
+use std::path::Path;
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+pub struct Session {
+    // rows of `.` (dead) and `O` (alive)
+    pub field: Vec<String>,
+    // the field is the part of an infinite universe holding every living cell
+    #[serde(default)]
+    pub infinite: bool,
+    pub generation: u64,
+    pub rule: String,
+    pub wrap: bool,
+    // cells beyond the edges of a field that does not wrap mirror those
+    // along them, rather than being dead
+    #[serde(default)]
+    pub mirror: bool,
+    pub cursor: (i64, i64),
+    pub step_interval_ms: u64,
+}
+
+impl Session {
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json).with_context(|| format!("failed to write {}", path.display()))
+    }
+
+    pub fn load(path: &Path) -> anyhow::Result<Session> {
+        let json = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        serde_json::from_str(&json).with_context(|| format!("failed to parse {}", path.display()))
+    }
+}
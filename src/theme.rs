@@ -0,0 +1,175 @@
+//! Color themes of the field on screen.
+
+use crossterm::style::Color;
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+pub enum ThemeName {
+    // age-colored cells in the terminal's own colors
+    Classic,
+    // white cells without an age gradient
+    Mono,
+    // green on black
+    Matrix,
+    // yellow newborns cooling through orange to red, on black
+    Fire,
+}
+
+impl ThemeName {
+    pub fn theme(self) -> Theme {
+        match self {
+            ThemeName::Classic => Theme {
+                dead: Color::Reset,
+                background: Color::Reset,
+                cursor: Color::Cyan,
+                ages: &[
+                    (1, Color::White),
+                    (3, Color::Yellow),
+                    (9, Color::Green),
+                    (29, Color::Blue),
+                    (u32::MAX, Color::DarkBlue),
+                ],
+            },
+            ThemeName::Mono => Theme {
+                dead: Color::DarkGrey,
+                background: Color::Reset,
+                cursor: Color::Grey,
+                ages: &[(u32::MAX, Color::White)],
+            },
+            ThemeName::Matrix => Theme {
+                dead: Color::Rgb { r: 0, g: 64, b: 0 },
+                background: Color::Black,
+                cursor: Color::White,
+                ages: &[(1, Color::Green), (u32::MAX, Color::DarkGreen)],
+            },
+            ThemeName::Fire => Theme {
+                dead: Color::Rgb { r: 64, g: 16, b: 0 },
+                background: Color::Black,
+                cursor: Color::Cyan,
+                ages: &[
+                    (1, Color::Yellow),
+                    (
+                        3,
+                        Color::Rgb {
+                            r: 255,
+                            g: 160,
+                            b: 0,
+                        },
+                    ),
+                    (
+                        9,
+                        Color::Rgb {
+                            r: 255,
+                            g: 96,
+                            b: 0,
+                        },
+                    ),
+                    (29, Color::Red),
+                    (u32::MAX, Color::DarkRed),
+                ],
+            },
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Theme {
+    pub dead: Color,
+    pub background: Color,
+    // the cell under the cursor in the normal view
+    pub cursor: Color,
+    // living cells up to each age, from newborns on
+    ages: &'static [(u32, Color)],
+}
+
+impl Theme {
+    /// Colors a living cell by its age, stepping from each color to the
+    /// next, or with `true_color` blending smoothly between them. The last
+    /// color is blended into by twice the age of the one before it.
+    pub fn alive(&self, age: u32, true_color: bool) -> Color {
+        let index = self
+            .ages
+            .iter()
+            .position(|&(max_age, _)| age <= max_age)
+            .unwrap_or(self.ages.len().saturating_sub(1));
+        let Some(&(_, color)) = self.ages.get(index) else {
+            return self.dead;
+        };
+        if !true_color || index == 0 {
+            return color;
+        }
+
+        let (from_age, from) = self.ages[index - 1];
+        let to_age = match self.ages[index].0 {
+            u32::MAX => from_age.saturating_mul(2),
+            to_age => to_age,
+        };
+        match (rgb(from), rgb(color)) {
+            (Some(from), Some(to)) if age < to_age => {
+                let share = (age - from_age) as f32 / (to_age - from_age) as f32;
+                let blend =
+                    |from: u8, to: u8| (from as f32 + (to as f32 - from as f32) * share) as u8;
+                Color::Rgb {
+                    r: blend(from.0, to.0),
+                    g: blend(from.1, to.1),
+                    b: blend(from.2, to.2),
+                }
+            }
+            _ => color,
+        }
+    }
+}
+
+/// Returns the red, green and blue of a color, as xterm draws the named
+/// ones, `None` for the terminal's own colors.
+fn rgb(color: Color) -> Option<(u8, u8, u8)> {
+    Some(match color {
+        Color::Rgb { r, g, b } => (r, g, b),
+        Color::Black => (0, 0, 0),
+        Color::DarkRed => (128, 0, 0),
+        Color::DarkGreen => (0, 128, 0),
+        Color::DarkYellow => (128, 128, 0),
+        Color::DarkBlue => (0, 0, 128),
+        Color::DarkMagenta => (128, 0, 128),
+        Color::DarkCyan => (0, 128, 128),
+        Color::Grey => (192, 192, 192),
+        Color::DarkGrey => (128, 128, 128),
+        Color::Red => (255, 0, 0),
+        Color::Green => (0, 255, 0),
+        Color::Yellow => (255, 255, 0),
+        Color::Blue => (0, 0, 255),
+        Color::Magenta => (255, 0, 255),
+        Color::Cyan => (0, 255, 255),
+        Color::White => (255, 255, 255),
+        _ => return None,
+    })
+}
+
+/// Colors the background of a cell by its `heat`, from 0 for no recent
+/// births and deaths to 1 for a birth or death every generation: blending
+/// from dark blue through red to yellow with `true_color`, otherwise in
+/// steps.
+pub fn heat(heat: f32, true_color: bool) -> Color {
+    const STOPS: [(f32, (u8, u8, u8)); 3] =
+        [(0.0, (0, 0, 96)), (0.5, (160, 0, 0)), (1.0, (255, 200, 0))];
+    let heat = heat.clamp(0.0, 1.0);
+    if !true_color {
+        return match heat {
+            ..0.34 => Color::DarkBlue,
+            ..0.67 => Color::DarkRed,
+            _ => Color::DarkYellow,
+        };
+    }
+    let index = STOPS
+        .iter()
+        .position(|&(at, _)| heat <= at)
+        .unwrap_or(0)
+        .max(1);
+    let ((from_at, from), (to_at, to)) = (STOPS[index - 1], STOPS[index]);
+    let share = (heat - from_at) / (to_at - from_at);
+    let blend = |from: u8, to: u8| (from as f32 + (to as f32 - from as f32) * share) as u8;
+    Color::Rgb {
+        r: blend(from.0, to.0),
+        g: blend(from.1, to.1),
+        b: blend(from.2, to.2),
+    }
+}
@@ -0,0 +1,842 @@
+//! Storage and stepping of the cells, either as a fixed grid or as an
+//! unbounded plane that grows with the pattern; [`crate::hashlife`] steps
+//! the plane much faster for regular patterns.
+
+use std::{any::Any, collections::HashMap};
+
+use rayon::prelude::*;
+
+use crate::rule::Rule;
+
+/// What lies beyond the edges of a finite universe.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Edges {
+    // the opposite edge, making the universe a torus
+    Wrap,
+    // dead cells
+    Dead,
+    // a mirror image of the cells along the edge
+    Mirror,
+}
+
+/// Which cells around a cell count as its neighbors.
+///
+/// Rules count living neighbors, so they mean something else in each
+/// neighborhood: with the 4 von Neumann neighbors, births and survivals
+/// above 4 can never happen.
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+pub enum Neighborhood {
+    // the 8 surrounding cells
+    Moore,
+    // the 4 orthogonally adjacent cells
+    #[value(name = "vonneumann", alias = "von-neumann")]
+    VonNeumann,
+}
+
+// offsets of the 8 neighbors in the Moore neighborhood
+const MOORE: [(i64, i64); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+
+impl Neighborhood {
+    /// Returns the offsets of the neighbors from a cell.
+    pub fn offsets(self) -> Vec<(i64, i64)> {
+        match self {
+            Neighborhood::Moore => MOORE.to_vec(),
+            Neighborhood::VonNeumann => vec![(-1, 0), (0, -1), (0, 1), (1, 0)],
+        }
+    }
+}
+
+/// Which side a living cell is on in the Immigration variant, where a cell
+/// is born to the side of most of its neighbors. Outside of it every cell
+/// is on side `A`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Player {
+    #[default]
+    A,
+    B,
+}
+
+/// Returns the side of most of `count` living neighbors, `b_count` of them
+/// on side B, and `A` on a tie.
+pub fn majority(count: usize, b_count: usize) -> Player {
+    if count < 2 * b_count {
+        Player::B
+    } else {
+        Player::A
+    }
+}
+
+pub trait Universe: Any {
+    /// Returns whether the cell at `(x, y)` is alive, cells beyond the edges
+    /// of a finite universe are dead.
+    fn is_alive(&self, x: i64, y: i64) -> bool;
+
+    /// Sets a cell and resets its age, ignoring cells beyond the edges of a
+    /// finite universe. Living cells are set on side `A`.
+    fn set(&mut self, x: i64, y: i64, alive: bool) {
+        if alive {
+            self.set_player(x, y, Player::A);
+        } else {
+            self.kill(x, y);
+        }
+    }
+
+    /// Brings a cell to life on the side of `player`, like [`Universe::set`].
+    fn set_player(&mut self, x: i64, y: i64, player: Player);
+
+    fn kill(&mut self, x: i64, y: i64);
+
+    /// Returns how many generations the cell has been alive for, 0 if dead.
+    fn age(&self, x: i64, y: i64) -> u32;
+
+    /// Returns the side of a living cell, `A` for dead ones.
+    fn player(&self, x: i64, y: i64) -> Player;
+
+    /// Computes the next generation, counting the cells at the `neighborhood`
+    /// offsets as neighbors, with `edges` beyond the edges of a finite
+    /// universe.
+    fn next(&self, rule: &Rule, edges: Edges, neighborhood: &[(i64, i64)]) -> Box<dyn Universe>;
+
+    /// Computes the next generation like [`Universe::next`], into the
+    /// storage of `spare`, a universe no longer needed, when it is of the
+    /// same kind.
+    fn next_into(
+        &self,
+        rule: &Rule,
+        edges: Edges,
+        neighborhood: &[(i64, i64)],
+        _spare: Box<dyn Universe>,
+    ) -> Box<dyn Universe> {
+        self.next(rule, edges, neighborhood)
+    }
+
+    /// Computes the generation `generations` on, like that many calls to
+    /// [`Universe::next`].
+    fn advance(
+        &self,
+        rule: &Rule,
+        edges: Edges,
+        neighborhood: &[(i64, i64)],
+        generations: u64,
+    ) -> Box<dyn Universe> {
+        if generations == 0 {
+            return self.clone_box();
+        }
+        let mut universe = self.next(rule, edges, neighborhood);
+        for _ in 1..generations {
+            universe = universe.next(rule, edges, neighborhood);
+        }
+        universe
+    }
+
+    fn live_cells(&self) -> Vec<(i64, i64)>;
+
+    fn clear(&mut self);
+
+    /// Returns `(width, height)` of a finite universe, `None` for an
+    /// infinite one.
+    fn bounds(&self) -> Option<(u16, u16)>;
+
+    fn clone_box(&self) -> Box<dyn Universe>;
+
+    /// Changes the size of a finite universe, keeping the cells that still
+    /// fit and padding the new area with dead ones.
+    fn resize(&mut self, width: u16, height: u16);
+
+    /// Counts the living cells at the `neighborhood` offsets from `(x, y)`,
+    /// with `edges` beyond the edges of a finite universe.
+    fn live_neighbors(&self, x: i64, y: i64, _edges: Edges, neighborhood: &[(i64, i64)]) -> usize {
+        neighborhood
+            .iter()
+            .filter(|&&(dx, dy)| self.is_alive(x.wrapping_add(dx), y.wrapping_add(dy)))
+            .count()
+    }
+
+    fn population(&self) -> usize {
+        self.live_cells().len()
+    }
+
+    fn same_cells(&self, other: &dyn Universe) -> bool {
+        let cells = self.live_cells();
+        cells.len() == other.population() && cells.iter().all(|&(x, y)| other.is_alive(x, y))
+    }
+
+    /// Returns a hash of the positions of the living cells, the same for
+    /// universes with the same living cells.
+    fn cells_hash(&self) -> u64 {
+        self.live_cells()
+            .into_iter()
+            .map(|(x, y)| cell_hash(x, y))
+            .fold(0, u64::wrapping_add)
+    }
+}
+
+/// Returns a hash of the position of a living cell, summed up over the
+/// cells to hash them in any order.
+fn cell_hash(x: i64, y: i64) -> u64 {
+    // the finalizer of splitmix64, past its increment so that the cell at
+    // the origin does not hash to 0 like no cell at all
+    let mut hash = ((x as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15) ^ y as u64)
+        .wrapping_add(0x9e37_79b9_7f4a_7c15);
+    hash = (hash ^ hash >> 30).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    hash = (hash ^ hash >> 27).wrapping_mul(0x94d0_49bb_1331_11eb);
+    hash ^ hash >> 31
+}
+
+/// A fixed `width` x `height` grid.
+#[derive(Clone)]
+pub struct Dense {
+    width: usize,
+    // a bit per cell, set for living ones, the first cell of each word in
+    // its lowest bit; the bits past the width are always clear
+    cells: Vec<Vec<u64>>,
+    // generations each cell has been alive for, 0 for dead cells
+    age: Vec<Vec<u32>>,
+    // sides of the living cells, `A` for dead ones
+    players: Vec<Vec<Player>>,
+}
+
+// cells in a word of a row
+const WORD: usize = u64::BITS as usize;
+
+/// Returns how many words a row of `width` cells takes.
+fn words(width: usize) -> usize {
+    width.div_ceil(WORD)
+}
+
+/// Returns the 64 bits of `row` from bit `start` on, the bits past its end
+/// clear.
+fn bits_from(row: &[u64], start: usize) -> u64 {
+    let (index, shift) = (start / WORD, start % WORD);
+    let low = row.get(index).copied().unwrap_or(0) >> shift;
+    if shift == 0 {
+        return low;
+    }
+    low | row.get(index + 1).copied().unwrap_or(0) << (WORD - shift)
+}
+
+impl Dense {
+    pub fn new(width: u16, height: u16) -> Dense {
+        let (width, height) = (width as usize, height as usize);
+        Dense {
+            width,
+            cells: vec![vec![0; words(width)]; height],
+            age: vec![vec![0; width]; height],
+            players: vec![vec![Player::A; width]; height],
+        }
+    }
+
+    /// Creates a grid with the living cells of `field`, as many cells wide as
+    /// its first row.
+    pub fn from_cells(field: &[Vec<bool>]) -> Dense {
+        let width = field.first().map_or(0, Vec::len);
+        let mut dense = Dense::new(width as u16, field.len() as u16);
+        for (y, field_row) in field.iter().enumerate() {
+            for (x, &alive) in field_row.iter().take(width).enumerate() {
+                dense.put(x, y, alive);
+            }
+        }
+        dense
+    }
+
+    /// Returns the bits of the living cells of each row, the first cell of
+    /// each word in its lowest bit.
+    pub fn rows(&self) -> &[Vec<u64>] {
+        &self.cells
+    }
+
+    pub fn into_cells(self) -> Vec<Vec<bool>> {
+        (0..self.cells.len())
+            .map(|y| (0..self.width).map(|x| self.get(x, y)).collect())
+            .collect()
+    }
+
+    fn get(&self, x: usize, y: usize) -> bool {
+        self.cells[y][x / WORD] >> (x % WORD) & 1 != 0
+    }
+
+    fn put(&mut self, x: usize, y: usize, alive: bool) {
+        let word = &mut self.cells[y][x / WORD];
+        let bit = 1 << (x % WORD);
+        if alive {
+            *word |= bit;
+        } else {
+            *word &= !bit;
+        }
+    }
+
+    fn index(&self, x: i64, y: i64) -> Option<(usize, usize)> {
+        let (x, y) = (usize::try_from(x).ok()?, usize::try_from(y).ok()?);
+        (x < self.width && y < self.cells.len()).then_some((x, y))
+    }
+
+    /// Counting the neighbors of a whole word at once works for the Moore
+    /// neighborhood without sides, like [`Dense::step_words`]. Every other
+    /// case goes cell by cell, where only living cells and their neighbors
+    /// are evaluated, since every other cell stays dead unless the rule gives
+    /// birth to cells without neighbors.
+    ///
+    /// With more than one thread in rayon's global pool, the rows are
+    /// evaluated in parallel.
+    pub fn step(&self, rule: &Rule, edges: Edges, neighborhood: &[(i64, i64)]) -> Dense {
+        let (width, height) = self.bounds().expect("a dense universe is finite");
+        let mut next = Dense::new(width, height);
+        self.step_into(rule, edges, neighborhood, &mut next);
+        next
+    }
+
+    /// Computes the next generation like [`Dense::step`], into `next` and
+    /// its storage. On one thread nothing is allocated unless `next` is of
+    /// another size.
+    pub fn step_into(
+        &self,
+        rule: &Rule,
+        edges: Edges,
+        neighborhood: &[(i64, i64)],
+        next: &mut Dense,
+    ) {
+        if next.bounds() != self.bounds() {
+            let (width, height) = self.bounds().expect("a dense universe is finite");
+            *next = Dense::new(width, height);
+        }
+        if self.width == 0 || self.cells.is_empty() {
+            return;
+        }
+        if neighborhood == MOORE && !self.has_side_b() {
+            return self.step_words(rule, edges, next);
+        }
+
+        if 1 < rayon::current_num_threads() {
+            next.cells
+                .par_iter_mut()
+                .zip(next.age.par_iter_mut())
+                .zip(next.players.par_iter_mut())
+                .enumerate()
+                .for_each(|(y, ((row, ages), players))| {
+                    row.fill(0);
+                    for (x, (age, player)) in ages.iter_mut().zip(players).enumerate() {
+                        let alive;
+                        (alive, *age, *player) = self.evaluate(x, y, rule, edges, neighborhood);
+                        row[x / WORD] |= u64::from(alive) << (x % WORD);
+                    }
+                });
+            return;
+        }
+
+        // the mirror images beyond the edges count as living cells too
+        if rule.birth[0] || edges == Edges::Mirror {
+            for y in 0..self.cells.len() {
+                for x in 0..self.width {
+                    let alive;
+                    (alive, next.age[y][x], next.players[y][x]) =
+                        self.evaluate(x, y, rule, edges, neighborhood);
+                    next.put(x, y, alive);
+                }
+            }
+            return;
+        }
+
+        for y in 0..self.cells.len() {
+            next.age[y].fill(0);
+            next.players[y].fill(Player::A);
+            for index in 0..words(self.width) {
+                let start = index * WORD;
+                let len = WORD.min(self.width - start);
+                // the cells that have a living cell as a neighbor
+                let mut active = 0;
+                for &(dx, dy) in neighborhood.iter().chain(&[(0, 0)]) {
+                    if let Some(row) = self.row_at(y as i64 + dy, edges) {
+                        active |= self.bits_at(start as i64 + dx, row, edges, len);
+                    }
+                }
+                if len < WORD {
+                    active &= (1 << len) - 1;
+                }
+
+                let mut word = 0;
+                while active != 0 {
+                    let bit = active.trailing_zeros() as usize;
+                    active &= active - 1;
+                    let x = start + bit;
+                    let alive;
+                    (alive, next.age[y][x], next.players[y][x]) =
+                        self.evaluate(x, y, rule, edges, neighborhood);
+                    word |= u64::from(alive) << bit;
+                }
+                next.cells[y][index] = word;
+            }
+        }
+    }
+
+    /// Steps the Moore neighborhood a word at a time: the 8 neighbors of the
+    /// 64 cells of a word are added up in bit-sliced counters, each of the
+    /// four holding a bit of every cell's count.
+    fn step_words(&self, rule: &Rule, edges: Edges, next: &mut Dense) {
+        let width = self.width;
+        let step_row = |y: usize, row: &mut [u64], ages: &mut [u32], players: &mut [Player]| {
+            let rows = [-1, 0, 1].map(|dy| self.row_at(y as i64 + dy, edges));
+            ages.fill(0);
+            players.fill(Player::A);
+            for (index, word) in row.iter_mut().enumerate() {
+                let start = index * WORD;
+                let len = WORD.min(width - start);
+                // away from the left and right edges, the bits are as stored
+                let inside = 0 < start && start + len < width;
+                let bits = |dx: i64, dy: usize| match rows[dy] {
+                    Some(row) if inside => {
+                        bits_from(&self.cells[row], start.wrapping_add_signed(dx as isize))
+                    }
+                    Some(row) => self.bits_at(start as i64 + dx, row, edges, len),
+                    None => 0,
+                };
+                let neighbors = [
+                    bits(-1, 0),
+                    bits(0, 0),
+                    bits(1, 0),
+                    bits(-1, 1),
+                    bits(1, 1),
+                    bits(-1, 2),
+                    bits(0, 2),
+                    bits(1, 2),
+                ];
+                let (mut ones, mut twos, mut fours, mut eights) = (0, 0, 0, 0);
+                for neighbor in neighbors {
+                    let carry = ones & neighbor;
+                    ones ^= neighbor;
+                    let carry_two = twos & carry;
+                    twos ^= carry;
+                    let carry_four = fours & carry_two;
+                    fours ^= carry_two;
+                    eights |= carry_four;
+                }
+
+                let alive = self.cells[y][index];
+                let mut next_word = 0;
+                for count in 0..=8 {
+                    let cells = match (rule.birth[count], rule.survival[count]) {
+                        (true, true) => !0,
+                        (true, false) => !alive,
+                        (false, true) => alive,
+                        (false, false) => continue,
+                    };
+                    let plane = |plane: u64, bit: usize| {
+                        if count & bit == 0 { !plane } else { plane }
+                    };
+                    next_word |= cells
+                        & plane(ones, 1)
+                        & plane(twos, 2)
+                        & plane(fours, 4)
+                        & plane(eights, 8);
+                }
+                if len < WORD {
+                    next_word &= (1 << len) - 1;
+                }
+                *word = next_word;
+
+                let mut born_or_kept = next_word;
+                while born_or_kept != 0 {
+                    let bit = born_or_kept.trailing_zeros() as usize;
+                    born_or_kept &= born_or_kept - 1;
+                    let x = start + bit;
+                    ages[x] = if alive >> bit & 1 != 0 {
+                        self.age[y][x].saturating_add(1)
+                    } else {
+                        1
+                    };
+                }
+            }
+        };
+
+        if 1 < rayon::current_num_threads() {
+            next.cells
+                .par_iter_mut()
+                .zip(next.age.par_iter_mut())
+                .zip(next.players.par_iter_mut())
+                .enumerate()
+                .for_each(|(y, ((row, ages), players))| step_row(y, row, ages, players));
+        } else {
+            for (y, ((row, ages), players)) in next
+                .cells
+                .iter_mut()
+                .zip(&mut next.age)
+                .zip(&mut next.players)
+                .enumerate()
+            {
+                step_row(y, row, ages, players);
+            }
+        }
+    }
+
+    /// Returns the row that row `y` stands for beyond the top and bottom
+    /// edges, `None` for dead ones.
+    fn row_at(&self, y: i64, edges: Edges) -> Option<usize> {
+        self.edge_index(0, y, edges).map(|(_, y)| y)
+    }
+
+    /// Returns the 64 cells of row `y` from cell `x` on as bits, the cells
+    /// beyond the left and right edges as `edges` makes them. Only the first
+    /// `len` bits count, the cells beyond the edges past them are left dead.
+    fn bits_at(&self, x: i64, y: usize, edges: Edges, len: usize) -> u64 {
+        let row = &self.cells[y];
+        let width = self.width as i64;
+        let (len, end) = (len as i64, x + len as i64);
+        let mut bits = match usize::try_from(x) {
+            Ok(x) => bits_from(row, x),
+            Err(_) => bits_from(row, 0)
+                .checked_shl(x.unsigned_abs() as u32)
+                .unwrap_or(0),
+        };
+        if edges == Edges::Dead || (0 <= x && end <= width) {
+            return bits;
+        }
+        let before = 0..(-x).clamp(0, len);
+        let after = (width - x).clamp(0, len)..len;
+        for bit in before.chain(after) {
+            if let Some((cell, _)) = self.edge_index(x + bit, y as i64, edges)
+                && self.get(cell, y)
+            {
+                bits |= 1 << bit;
+            }
+        }
+        bits
+    }
+
+    /// Returns whether `f` holds for every living cell, going row by row and
+    /// stopping at the first it does not hold for.
+    fn all_living(&self, mut f: impl FnMut(usize, usize) -> bool) -> bool {
+        for (y, row) in self.cells.iter().enumerate() {
+            for (index, &word) in row.iter().enumerate() {
+                let mut word = word;
+                while word != 0 {
+                    if !f(index * WORD + word.trailing_zeros() as usize, y) {
+                        return false;
+                    }
+                    word &= word - 1;
+                }
+            }
+        }
+        true
+    }
+
+    /// Returns whether any living cell is on side B.
+    fn has_side_b(&self) -> bool {
+        self.players.iter().any(|row| row.contains(&Player::B))
+    }
+
+    /// Returns the next state, age and side of the cell at `(x, y)`.
+    fn evaluate(
+        &self,
+        x: usize,
+        y: usize,
+        rule: &Rule,
+        edges: Edges,
+        neighborhood: &[(i64, i64)],
+    ) -> (bool, u32, Player) {
+        let live_neighbors = self.live_neighbors(x as i64, y as i64, edges, neighborhood);
+
+        let current_cell_alive = self.get(x, y);
+        let alive = rule.next_state(current_cell_alive, live_neighbors);
+
+        match (current_cell_alive, alive) {
+            (true, true) => (true, self.age[y][x].saturating_add(1), self.players[y][x]),
+            (false, true) => {
+                let b_count = neighborhood
+                    .iter()
+                    .filter_map(|&(dx, dy)| self.edge_index(x as i64 + dx, y as i64 + dy, edges))
+                    .filter(|&(x, y)| self.get(x, y) && self.players[y][x] == Player::B)
+                    .count();
+                (true, 1, majority(live_neighbors, b_count))
+            }
+            (_, false) => (false, 0, Player::A),
+        }
+    }
+
+    /// Like [`Dense::index`], but finding the cell a position beyond the
+    /// edges stands for.
+    fn edge_index(&self, x: i64, y: i64, edges: Edges) -> Option<(usize, usize)> {
+        let width = self.width as i64;
+        let height = self.cells.len() as i64;
+        match edges {
+            Edges::Wrap => self.index(x.rem_euclid(width), y.rem_euclid(height)),
+            Edges::Dead => self.index(x, y),
+            Edges::Mirror => {
+                let reflect = |position: i64, size: i64| {
+                    if position < 0 {
+                        -1 - position
+                    } else if size <= position {
+                        2 * size - 1 - position
+                    } else {
+                        position
+                    }
+                };
+                self.index(reflect(x, width), reflect(y, height))
+            }
+        }
+    }
+}
+
+impl Universe for Dense {
+    fn is_alive(&self, x: i64, y: i64) -> bool {
+        self.index(x, y).is_some_and(|(x, y)| self.get(x, y))
+    }
+
+    fn set_player(&mut self, x: i64, y: i64, player: Player) {
+        if let Some((x, y)) = self.index(x, y) {
+            self.put(x, y, true);
+            self.age[y][x] = 0;
+            self.players[y][x] = player;
+        }
+    }
+
+    fn kill(&mut self, x: i64, y: i64) {
+        if let Some((x, y)) = self.index(x, y) {
+            self.put(x, y, false);
+            self.age[y][x] = 0;
+            self.players[y][x] = Player::A;
+        }
+    }
+
+    fn age(&self, x: i64, y: i64) -> u32 {
+        self.index(x, y).map_or(0, |(x, y)| self.age[y][x])
+    }
+
+    fn player(&self, x: i64, y: i64) -> Player {
+        self.index(x, y)
+            .map_or(Player::A, |(x, y)| self.players[y][x])
+    }
+
+    fn next(&self, rule: &Rule, edges: Edges, neighborhood: &[(i64, i64)]) -> Box<dyn Universe> {
+        Box::new(self.step(rule, edges, neighborhood))
+    }
+
+    fn next_into(
+        &self,
+        rule: &Rule,
+        edges: Edges,
+        neighborhood: &[(i64, i64)],
+        mut spare: Box<dyn Universe>,
+    ) -> Box<dyn Universe> {
+        let any: &mut dyn Any = &mut *spare;
+        match any.downcast_mut::<Dense>() {
+            Some(next) => {
+                self.step_into(rule, edges, neighborhood, next);
+                spare
+            }
+            None => self.next(rule, edges, neighborhood),
+        }
+    }
+
+    fn live_cells(&self) -> Vec<(i64, i64)> {
+        let mut cells = Vec::new();
+        self.all_living(|x, y| {
+            cells.push((x as i64, y as i64));
+            true
+        });
+        cells
+    }
+
+    fn clear(&mut self) {
+        for row in &mut self.cells {
+            row.fill(0);
+        }
+        for row in &mut self.age {
+            row.fill(0);
+        }
+        for row in &mut self.players {
+            row.fill(Player::A);
+        }
+    }
+
+    fn bounds(&self) -> Option<(u16, u16)> {
+        Some((self.width as u16, self.cells.len() as u16))
+    }
+
+    fn clone_box(&self) -> Box<dyn Universe> {
+        Box::new(self.clone())
+    }
+
+    fn resize(&mut self, width: u16, height: u16) {
+        let narrower = (width as usize) < self.width;
+        self.width = width as usize;
+        for row in &mut self.cells {
+            row.resize(words(self.width), 0);
+            // the cells cut off at the end of the last word
+            if narrower && !self.width.is_multiple_of(WORD) {
+                *row.last_mut().expect("a narrower row keeps a word") &=
+                    (1 << (self.width % WORD)) - 1;
+            }
+        }
+        for row in &mut self.age {
+            row.resize(width as usize, 0);
+        }
+        for row in &mut self.players {
+            row.resize(width as usize, Player::A);
+        }
+        self.cells
+            .resize(height as usize, vec![0; words(self.width)]);
+        self.age.resize(height as usize, vec![0; width as usize]);
+        self.players
+            .resize(height as usize, vec![Player::A; width as usize]);
+    }
+
+    fn live_neighbors(&self, x: i64, y: i64, edges: Edges, neighborhood: &[(i64, i64)]) -> usize {
+        neighborhood
+            .iter()
+            .filter(|&&(dx, dy)| {
+                self.edge_index(x + dx, y + dy, edges)
+                    .is_some_and(|(x, y)| self.get(x, y))
+            })
+            .count()
+    }
+
+    fn population(&self) -> usize {
+        self.cells
+            .iter()
+            .flatten()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    fn same_cells(&self, other: &dyn Universe) -> bool {
+        self.population() == other.population()
+            && self.all_living(|x, y| other.is_alive(x as i64, y as i64))
+    }
+
+    fn cells_hash(&self) -> u64 {
+        let mut hash = 0u64;
+        self.all_living(|x, y| {
+            hash = hash.wrapping_add(cell_hash(x as i64, y as i64));
+            true
+        });
+        hash
+    }
+}
+
+/// A grid stepped back and forth between two of its size, the next
+/// generation computed into the back one and swapped to the front, so that
+/// stepping on one thread allocates nothing.
+pub struct DoubleBuffer {
+    front: Dense,
+    back: Dense,
+}
+
+impl DoubleBuffer {
+    pub fn new(front: Dense) -> DoubleBuffer {
+        let (width, height) = front.bounds().expect("a dense universe is finite");
+        DoubleBuffer {
+            front,
+            back: Dense::new(width, height),
+        }
+    }
+
+    pub fn step(&mut self, rule: &Rule, edges: Edges, neighborhood: &[(i64, i64)]) {
+        self.front
+            .step_into(rule, edges, neighborhood, &mut self.back);
+        std::mem::swap(&mut self.front, &mut self.back);
+    }
+
+    /// Returns the current generation.
+    pub fn front(&self) -> &Dense {
+        &self.front
+    }
+
+    /// Returns the previous generation, which the next step overwrites.
+    pub fn back(&self) -> &Dense {
+        &self.back
+    }
+}
+
+/// An unbounded plane that only stores its living cells.
+#[derive(Clone, Default)]
+pub struct Sparse {
+    // the living cells, how many generations they have been alive for and
+    // their sides
+    cells: HashMap<(i64, i64), (u32, Player)>,
+}
+
+impl Universe for Sparse {
+    fn is_alive(&self, x: i64, y: i64) -> bool {
+        self.cells.contains_key(&(x, y))
+    }
+
+    fn set_player(&mut self, x: i64, y: i64, player: Player) {
+        self.cells.insert((x, y), (0, player));
+    }
+
+    fn kill(&mut self, x: i64, y: i64) {
+        self.cells.remove(&(x, y));
+    }
+
+    fn age(&self, x: i64, y: i64) -> u32 {
+        self.cells.get(&(x, y)).map_or(0, |&(age, _)| age)
+    }
+
+    fn player(&self, x: i64, y: i64) -> Player {
+        self.cells
+            .get(&(x, y))
+            .map_or(Player::A, |&(_, player)| player)
+    }
+
+    /// Only living cells and their neighbors can be alive in the next
+    /// generation, so `rule` must not give birth to cells without neighbors.
+    fn next(&self, rule: &Rule, _edges: Edges, neighborhood: &[(i64, i64)]) -> Box<dyn Universe> {
+        // the living neighbors of each cell, and how many of them are on side B
+        let mut live_neighbors: HashMap<(i64, i64), (usize, usize)> = HashMap::new();
+        for (&(x, y), &(_, player)) in &self.cells {
+            live_neighbors.entry((x, y)).or_default();
+            for &(dx, dy) in neighborhood {
+                let (count, b_count) = live_neighbors
+                    .entry((x.wrapping_sub(dx), y.wrapping_sub(dy)))
+                    .or_default();
+                *count += 1;
+                *b_count += usize::from(player == Player::B);
+            }
+        }
+
+        let cells = live_neighbors
+            .into_iter()
+            .filter_map(|(position, (count, b_count))| {
+                let cell = self.cells.get(&position);
+                rule.next_state(cell.is_some(), count).then(|| {
+                    let next = match cell {
+                        Some(&(age, player)) => (age.saturating_add(1), player),
+                        None => (1, majority(count, b_count)),
+                    };
+                    (position, next)
+                })
+            })
+            .collect();
+        Box::new(Sparse { cells })
+    }
+
+    fn live_cells(&self) -> Vec<(i64, i64)> {
+        self.cells.keys().copied().collect()
+    }
+
+    fn clear(&mut self) {
+        self.cells.clear();
+    }
+
+    fn bounds(&self) -> Option<(u16, u16)> {
+        None
+    }
+
+    fn clone_box(&self) -> Box<dyn Universe> {
+        Box::new(self.clone())
+    }
+
+    fn resize(&mut self, _width: u16, _height: u16) {}
+
+    fn population(&self) -> usize {
+        self.cells.len()
+    }
+}